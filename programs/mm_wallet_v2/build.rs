@@ -0,0 +1,4 @@
+fn main() {
+    // The entrypoint generated by #[program] checks for the Solana BPF target
+    println!("cargo::rustc-check-cfg=cfg(target_os, values(\"solana\"))");
+}
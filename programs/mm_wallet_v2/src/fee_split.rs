@@ -0,0 +1,148 @@
+// ═══════════════════════════════════════════════════════════════════════════════
+// FEE SPLIT
+// Per-wallet table sharing claimed creator fees among beneficiaries by bps weight
+// ═══════════════════════════════════════════════════════════════════════════════
+
+use anchor_lang::prelude::*;
+
+use crate::MmWalletError;
+
+/// Maximum recipients per fee split
+pub const MAX_FEE_RECIPIENTS: usize = 8;
+
+/// Total weight of a fee split
+pub const FEE_SPLIT_TOTAL_BPS: u16 = 10_000;
+
+/// A beneficiary and its weight, as set by the owner
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct FeeShare {
+    /// Account receiving the share
+    pub key: Pubkey,
+    
+    /// Share of each distribution in bps
+    pub bps: u16,
+}
+
+/// One beneficiary of the creator fees
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct FeeRecipient {
+    /// Account receiving the share
+    pub key: Pubkey,
+    
+    /// Share of each distribution in bps
+    pub bps: u16,
+    
+    /// Lamports paid to this recipient so far
+    pub paid: u64,
+}
+
+impl FeeRecipient {
+    pub const SIZE: usize = 32 + 2 + 8;
+}
+
+/// How a wallet's claimed creator fees are shared out
+#[account]
+pub struct FeeSplit {
+    /// MM wallet whose fees are split
+    pub wallet: Pubkey,
+    
+    /// PDA bump seed
+    pub bump: u8,
+    
+    /// Beneficiaries, paid in this order
+    pub recipients: Vec<FeeRecipient>,
+    
+    /// Claimed fees paid out or predating the split
+    /// (undistributed = `MmWallet.total_fees_claimed - distributed`)
+    pub distributed: u64,
+    
+    /// Reserved space for future upgrades
+    pub reserved: [u8; 32],
+}
+
+impl FeeSplit {
+    pub const SIZE: usize = 8 +  // discriminator
+        32 +  // wallet
+        1 +   // bump
+        4 + FeeRecipient::SIZE * MAX_FEE_RECIPIENTS +  // recipients
+        8 +   // distributed
+        32;   // reserved
+    
+    /// Replace the table, keeping the payout history of recipients that stay
+    pub fn set_recipients(&mut self, shares: &[FeeShare]) -> Result<()> {
+        require!(
+            !shares.is_empty() && shares.len() <= MAX_FEE_RECIPIENTS,
+            MmWalletError::InvalidFeeSplit
+        );
+        
+        let mut total: u32 = 0;
+        for (i, share) in shares.iter().enumerate() {
+            require!(share.key != Pubkey::default() && share.bps > 0, MmWalletError::InvalidFeeSplit);
+            require!(
+                shares[..i].iter().all(|other| other.key != share.key),
+                MmWalletError::InvalidFeeSplit
+            );
+            total += share.bps as u32;
+        }
+        require!(total == FEE_SPLIT_TOTAL_BPS as u32, MmWalletError::InvalidFeeSplit);
+        
+        self.recipients = shares
+            .iter()
+            .map(|share| FeeRecipient {
+                key: share.key,
+                bps: share.bps,
+                paid: self.recipients.iter().find(|r| r.key == share.key).map_or(0, |r| r.paid),
+            })
+            .collect();
+        
+        Ok(())
+    }
+    
+    /// Install the first table; fees claimed before the split existed stay with the wallet
+    pub fn init_table(&mut self, shares: &[FeeShare], total_fees_claimed: u64) -> Result<()> {
+        self.set_recipients(shares)?;
+        self.distributed = total_fees_claimed;
+        
+        Ok(())
+    }
+    
+    /// Replace the table once the current one has been paid what it is owed
+    /// 
+    /// Fees claimed under a table belong to its recipients, so a changed table is rejected
+    /// while any of them is still pending (rounding dust aside, which carries over).
+    pub fn set_table(&mut self, shares: &[FeeShare], total_fees_claimed: u64) -> Result<()> {
+        let unchanged = self.recipients.len() == shares.len()
+            && self.recipients.iter().zip(shares).all(|(r, s)| r.key == s.key && r.bps == s.bps);
+        let pending = self.shares(self.undistributed(total_fees_claimed)).iter().any(|s| *s > 0);
+        require!(unchanged || !pending, MmWalletError::FeesPendingDistribution);
+        
+        self.set_recipients(shares)
+    }
+    
+    /// Claimed fees not yet distributed
+    pub fn undistributed(&self, total_fees_claimed: u64) -> u64 {
+        total_fees_claimed.saturating_sub(self.distributed)
+    }
+    
+    /// Each recipient's share of `amount`, in table order
+    /// 
+    /// Shares round down; the dust stays undistributed and rolls into the next payout.
+    pub fn shares(&self, amount: u64) -> Vec<u64> {
+        self.recipients
+            .iter()
+            .map(|r| ((amount as u128) * (r.bps as u128) / FEE_SPLIT_TOTAL_BPS as u128) as u64)
+            .collect()
+    }
+    
+    /// Record a payout computed by `shares`
+    pub fn record_payout(&mut self, shares: &[u64]) -> u64 {
+        let mut total = 0u64;
+        for (recipient, share) in self.recipients.iter_mut().zip(shares) {
+            recipient.paid = recipient.paid.saturating_add(*share);
+            total = total.saturating_add(*share);
+        }
+        self.distributed = self.distributed.saturating_add(total);
+        
+        total
+    }
+}
@@ -4,8 +4,37 @@
 // ═══════════════════════════════════════════════════════════════════════════════
 
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
 use anchor_lang::solana_program::{program::invoke_signed, system_instruction};
-use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use anchor_spl::token::spl_token::native_mint;
+use anchor_spl::associated_token::{self, get_associated_token_address, AssociatedToken};
+use anchor_spl::token::{self, CloseAccount, Mint, Token, TokenAccount, Transfer};
+
+pub mod fee_split;
+pub mod multisig;
+pub mod operators;
+pub mod protocol;
+pub mod pump_fun;
+pub mod pumpswap;
+pub mod recovery;
+pub mod risk;
+pub mod sessions;
+pub mod timelock;
+pub mod vesting;
+
+use fee_split::{FeeShare, FeeSplit};
+use multisig::{OwnerMultisig, Proposal, ProposalAction};
+use operators::{
+    OperatorEntry, OperatorRegistry, Permissions, ROLE_BUY, ROLE_CLAIM_FEES, ROLE_PAUSE, ROLE_SELL, ROLE_SWAP,
+};
+use protocol::{ConfigBounds, ProtocolConfig, ProtocolConfigParams};
+use pump_fun::{BondingCurveState, CurveTradeAccounts};
+use pumpswap::SwapTradeAccounts;
+use recovery::RecoveryConfig;
+use risk::{RiskLimits, RiskLimitsConfig};
+use sessions::SessionKey;
+use timelock::{ConfigChange, PendingConfigChange, CONFIG_CHANGE_DELAY_SECONDS};
+use vesting::{VestingParams, VestingSchedule};
 
 declare_id!("4ZzKbBw9o1CuVgGVokLNWsgHy9Acnd4EzVH5N6nnbyf5");
 
@@ -46,8 +75,26 @@ pub const MIN_SLIPPAGE_BPS: u16 = 10;
 /// Maximum slippage allowed (50%)
 pub const MAX_SLIPPAGE_BPS: u16 = 5000;
 
+/// Default deviation allowed between caller's expected output and on-chain quote (2%)
+pub const DEFAULT_QUOTE_TOLERANCE_BPS: u16 = 200;
+
+/// Maximum deviation allowed between caller's expected output and on-chain quote (20%)
+pub const MAX_QUOTE_TOLERANCE_BPS: u16 = 2000;
+
+/// Fixed-point scale for average entry price (lamports per token, scaled)
+pub const PRICE_SCALE: u128 = 1_000_000_000;
+
+/// Reason codes recorded by `guardian_pause`
+pub mod guardian_reason {
+    pub const UNSPECIFIED: u8 = 0;
+    pub const EXPLOIT: u8 = 1;
+    pub const VENUE_CHANGE: u8 = 2;
+    pub const OPERATOR_COMPROMISED: u8 = 3;
+    pub const ABNORMAL_TRADING: u8 = 4;
+}
+
 /// Program version for migrations
-pub const PROGRAM_VERSION: u8 = 2;
+pub const PROGRAM_VERSION: u8 = 3;
 
 // Pump.fun instruction discriminators (documented)
 pub const PUMP_BUY_DISCRIMINATOR: [u8; 8] = [102, 6, 61, 18, 1, 218, 235, 234];
@@ -65,6 +112,7 @@ pub struct WalletInitialized {
     pub wallet: Pubkey,
     pub lock_until: i64,
     pub strategy: u8,
+    pub commitment_bps: u16,
 }
 
 #[event]
@@ -81,19 +129,49 @@ pub struct Withdrawn {
     pub amount: u64,
 }
 
+#[event]
+pub struct ProfitsWithdrawn {
+    pub wallet: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub principal: u64, // left locked
+}
+
 #[event]
 pub struct TradeExecuted {
     pub wallet: Pubkey,
     pub trade_type: u8, // 0=buy, 1=sell
+    pub venue: u8,      // 0=bonding curve, 1=PumpSwap
     pub amount_in: u64,
     pub min_amount_out: u64,
+    pub realized_in: u64,  // measured from PDA balance deltas
+    pub realized_out: u64, // measured from PDA balance deltas
+    pub token_inventory: u64,
+    pub avg_entry_price: u64, // lamports per token, scaled by PRICE_SCALE
+    pub realized_pnl: i64,
     pub timestamp: i64,
 }
 
 #[event]
 pub struct FeesClaimed {
+    pub wallet: Pubkey,
+    pub venue: u8, // 0 = Pump.fun, 1 = PumpSwap
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeSplitUpdated {
+    pub wallet: Pubkey,
+    pub shares: Vec<FeeShare>,
+}
+
+#[event]
+pub struct FeesDistributed {
     pub wallet: Pubkey,
     pub amount: u64,
+    pub payouts: Vec<u64>, // per recipient, in table order
+    pub undistributed: u64,
     pub timestamp: i64,
 }
 
@@ -111,6 +189,274 @@ pub struct OperatorChanged {
     pub new_operator: Pubkey,
 }
 
+#[event]
+pub struct ConfigChangeQueued {
+    pub wallet: Pubkey,
+    pub kind: u8,
+    pub change: ConfigChange,
+    pub queued_at: i64,
+    pub eta: i64,
+}
+
+#[event]
+pub struct ConfigChangeApplied {
+    pub wallet: Pubkey,
+    pub kind: u8,
+}
+
+#[event]
+pub struct ConfigChangeCancelled {
+    pub wallet: Pubkey,
+    pub kind: u8,
+}
+
+#[event]
+pub struct MultisigUpdated {
+    pub wallet: Pubkey,
+    pub owners: Vec<Pubkey>,
+    pub threshold: u8,
+    pub generation: u32,
+}
+
+#[event]
+pub struct ProposalCreated {
+    pub wallet: Pubkey,
+    pub proposal: Pubkey,
+    pub index: u64,
+    pub proposer: Pubkey,
+    pub kind: u8,
+}
+
+#[event]
+pub struct ProposalApproved {
+    pub wallet: Pubkey,
+    pub proposal: Pubkey,
+    pub owner: Pubkey,
+    pub approvals: u8,
+}
+
+#[event]
+pub struct ProposalExecuted {
+    pub wallet: Pubkey,
+    pub proposal: Pubkey,
+    pub executor: Pubkey,
+    pub kind: u8,
+}
+
+#[event]
+pub struct ProposalClosed {
+    pub wallet: Pubkey,
+    pub proposal: Pubkey,
+    pub executed: bool,
+}
+
+#[event]
+pub struct RecoveryConfigUpdated {
+    pub wallet: Pubkey,
+    pub guardians: Vec<Pubkey>,
+    pub threshold: u8,
+    pub challenge_seconds: i64,
+}
+
+#[event]
+pub struct RecoveryStarted {
+    pub wallet: Pubkey,
+    pub guardian: Pubkey,
+    pub new_owner: Pubkey,
+    pub approvals: u8,
+    pub executable_at: i64,
+}
+
+#[event]
+pub struct RecoverySupported {
+    pub wallet: Pubkey,
+    pub guardian: Pubkey,
+    pub new_owner: Pubkey,
+    pub approvals: u8,
+    pub executable_at: i64,
+}
+
+#[event]
+pub struct RecoveryVetoed {
+    pub wallet: Pubkey,
+    pub owner: Pubkey,
+    pub new_owner: Pubkey,
+}
+
+#[event]
+pub struct RecoveryExecuted {
+    pub wallet: Pubkey,
+    pub old_owner: Pubkey,
+    pub new_owner: Pubkey,
+}
+
+#[event]
+pub struct GuardianChanged {
+    pub wallet: Pubkey,
+    pub old_guardian: Pubkey,
+    pub new_guardian: Pubkey,
+}
+
+#[event]
+pub struct GuardianPaused {
+    pub wallet: Pubkey,
+    pub guardian: Pubkey,
+    pub reason: u8, // see `guardian_reason`
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OperatorAdded {
+    pub wallet: Pubkey,
+    pub operator: Pubkey,
+    pub roles: u8,
+    pub max_slippage_bps: u16,
+    pub max_trade_lamports: u64,
+}
+
+#[event]
+pub struct OperatorUpdated {
+    pub wallet: Pubkey,
+    pub operator: Pubkey,
+    pub roles: u8,
+    pub max_slippage_bps: u16,
+    pub max_trade_lamports: u64,
+}
+
+#[event]
+pub struct OperatorRemoved {
+    pub wallet: Pubkey,
+    pub operator: Pubkey,
+}
+
+#[event]
+pub struct SessionCreated {
+    pub wallet: Pubkey,
+    pub session_key: Pubkey,
+    pub roles: u8,
+    pub expires_at: i64,
+    pub expires_at_slot: u64,
+    pub allowance_lamports: u64,
+}
+
+#[event]
+pub struct SessionRevoked {
+    pub wallet: Pubkey,
+    pub session_key: Pubkey,
+    pub exhausted: bool, // false = revoked by owner
+}
+
+#[event]
+pub struct ProtocolConfigUpdated {
+    pub admin: Pubkey,
+    pub guardian: Pubkey,
+    pub allowed_programs: Vec<Pubkey>,
+    pub max_trade_pct: u8,
+    pub min_slippage_bps: u16,
+    pub max_slippage_bps: u16,
+    pub treasury: Pubkey,
+    pub fee_bps_by_tier: [u16; protocol::FEE_TIERS],
+}
+
+#[event]
+pub struct ProtocolFeeCharged {
+    pub wallet: Pubkey,
+    pub treasury: Pubkey,
+    pub fee_tier: u8,
+    pub notional: u64,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeTierChanged {
+    pub wallet: Pubkey,
+    pub old_tier: u8,
+    pub new_tier: u8,
+}
+
+#[event]
+pub struct ProtocolAdminChanged {
+    pub old_admin: Pubkey,
+    pub new_admin: Pubkey,
+}
+
+#[event]
+pub struct ProtocolPauseChanged {
+    pub by: Pubkey,
+    pub paused: bool,
+    pub reason: u8, // see `guardian_reason`; 0 on resume
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OwnershipTransferProposed {
+    pub wallet: Pubkey,
+    pub owner: Pubkey,
+    pub pending_owner: Pubkey,
+}
+
+#[event]
+pub struct OwnershipTransferred {
+    pub wallet: Pubkey,
+    pub old_owner: Pubkey,
+    pub new_owner: Pubkey,
+}
+
+#[event]
+pub struct VestingUpdated {
+    pub wallet: Pubkey,
+    pub start: i64,
+    pub cliff: i64,
+    pub end: i64,
+    pub locked_principal: u64,
+}
+
+#[event]
+pub struct TokenLockUpdated {
+    pub wallet: Pubkey,
+    pub start: i64,
+    pub cliff: i64,
+    pub end: i64,
+    pub locked_tokens: u64,
+}
+
+#[event]
+pub struct TokensWithdrawn {
+    pub wallet: Pubkey,
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub remaining: u64,
+    pub locked: u64,
+}
+
+#[event]
+pub struct WalletMigrated {
+    pub wallet: Pubkey,
+    pub version: u8,
+}
+
+#[event]
+pub struct RiskLimitsUpdated {
+    pub wallet: Pubkey,
+    pub max_daily_buy_lamports: u64,
+    pub max_daily_sell_lamports: u64,
+    pub max_trade_lamports: u64,
+    pub max_token_position: u64,
+    pub max_sell_tokens: u64,
+    pub max_drawdown_bps: u16,
+}
+
+#[event]
+pub struct CircuitBreakerTripped {
+    pub wallet: Pubkey,
+    pub equity: u64,
+    pub high_water_mark: u64,
+    pub drawdown_bps: u64,
+    pub timestamp: i64,
+}
+
 // ═══════════════════════════════════════════════════════════════════════════════
 // STATE ACCOUNTS
 // ═══════════════════════════════════════════════════════════════════════════════
@@ -151,7 +497,7 @@ pub struct MmWallet {
     /// Whether this wallet created the token (receives creator fees)
     pub is_creator: bool,
     
-    /// Total SOL volume traded (for stats)
+    /// Total SOL volume traded in lamports, buys plus sells (for stats)
     pub total_volume: u64,
     
     /// Total trades executed
@@ -166,8 +512,75 @@ pub struct MmWallet {
     /// Creation timestamp
     pub created_at: i64,
     
+    /// Lamports spent on buys (measured)
+    pub sol_in: u64,
+    
+    /// Lamports received from sells (measured)
+    pub sol_out: u64,
+    
+    /// Tokens received from buys (measured)
+    pub tokens_bought: u64,
+    
+    /// Tokens spent on sells (measured)
+    pub tokens_sold: u64,
+    
+    /// Tokens held at tracked cost (bought through this program)
+    pub token_inventory: u64,
+    
+    /// Lamports paid for the current token inventory
+    pub cost_basis: u64,
+    
+    /// Realized PnL in lamports (sell proceeds minus average cost)
+    pub realized_pnl: i64,
+    
+    /// Key the PDA seeds are derived from (owner at creation, never changes)
+    pub seed_owner: Pubkey,
+    
+    /// Owner proposed by `propose_owner`, pending acceptance
+    pub pending_owner: Pubkey,
+    
+    /// Key that can pause trading and nothing else (default = none)
+    pub guardian: Pubkey,
+    
+    /// Protocol fee tier, set by the protocol admin (index into `fee_bps_by_tier`)
+    pub fee_tier: u8,
+    
+    /// Total protocol fees paid to the treasury
+    pub total_protocol_fees: u64,
+    
+    /// Part of `total_fees_claimed` collected from PumpSwap
+    pub swap_fees_claimed: u64,
+    
+    /// Linear unlock of SOL principal (unset = `lock_until` cliff only)
+    pub vesting: VestingSchedule,
+    
+    /// Committed SOL: deposits less principal withdrawn (everything above it is profit)
+    pub principal: u64,
+    
+    /// Total profits withdrawn through `withdraw_profits`
+    pub profits_withdrawn: u64,
+    
+    /// Lock on the PDA's tokens, on top of `lock_until` (unset = `lock_until` only)
+    pub token_vesting: VestingSchedule,
+    
+    /// Liquidity commitment: max net tokens sold per rolling 24h while locked, in bps
+    /// of inventory (0 = none). Set at `initialize`, lapses at `lock_until`.
+    pub commitment_bps: u16,
+    
+    /// Sensitive owner actions need an approved proposal from the `OwnerMultisig`
+    pub multisig: bool,
+    
+    /// Trades must carry the `RiskLimits` account (set by `init_risk_limits`)
+    pub risk_limits: bool,
+    
+    /// Profit withdrawals must carry the `FeeSplit` account (set by `init_fee_split`)
+    pub fee_split: bool,
+    
+    /// Operator calls must carry the `OperatorRegistry` account (set by `init_operator_registry`)
+    pub operator_registry: bool,
+    
     /// Reserved space for future upgrades
-    pub reserved: [u8; 64],
+    pub reserved: [u8; 1],
 }
 
 impl MmWallet {
@@ -188,11 +601,115 @@ impl MmWallet {
         8 +   // total_fees_claimed
         8 +   // last_trade
         8 +   // created_at
-        64;   // reserved
+        8 +   // sol_in
+        8 +   // sol_out
+        8 +   // tokens_bought
+        8 +   // tokens_sold
+        8 +   // token_inventory
+        8 +   // cost_basis
+        8 +   // realized_pnl
+        32 +  // seed_owner
+        32 +  // pending_owner
+        32 +  // guardian
+        1 +   // fee_tier
+        8 +   // total_protocol_fees
+        8 +   // swap_fees_claimed
+        VestingSchedule::SIZE +  // vesting
+        8 +   // principal
+        8 +   // profits_withdrawn
+        VestingSchedule::SIZE +  // token_vesting
+        2 +   // commitment_bps
+        1 +   // multisig
+        1 +   // risk_limits
+        1 +   // fee_split
+        1 +   // operator_registry
+        1;    // reserved
+    
+    /// v2 layout: 192 bytes shorter, ending with 8 reserved bytes after `realized_pnl`
+    pub const V2_SIZE: usize = Self::SIZE - 192;
+    
+    /// Resolve what `caller` may do: owner, registered operator, then legacy operator
+    /// 
+    /// Once the wallet has a registry it must be passed, so an operator can't leave it
+    /// out to fall back to the legacy operator's unrestricted roles.
+    pub fn permissions(&self, caller: &Pubkey, registry: Option<&OperatorRegistry>) -> Result<Permissions> {
+        if *caller == self.owner {
+            return Ok(Permissions::OWNER);
+        }
+        
+        require!(
+            registry.is_some() || !self.operator_registry,
+            MmWalletError::OperatorRegistryRequired
+        );
+        
+        if let Some(entry) = registry.and_then(|r| r.find(caller)) {
+            return Ok(entry.into());
+        }
+        
+        require!(*caller == self.operator, MmWalletError::UnauthorizedOperator);
+        
+        Ok(Permissions::LEGACY_OPERATOR)
+    }
+    
+    /// Check `signer` may run a multisig-gated owner `action`
+    /// 
+    /// Without a multisig the owner signs alone. With one, any multisig owner may submit
+    /// a proposal approving exactly `action`, which is then marked executed. Owners and
+    /// multisig owners can be PDAs of other programs signing via CPI.
+    pub fn authorize_owner_action(
+        &self,
+        signer: &Pubkey,
+        multisig: Option<&OwnerMultisig>,
+        proposal: Option<&mut Proposal>,
+        action: &ProposalAction,
+    ) -> Result<()> {
+        if !self.multisig {
+            require!(*signer == self.owner, MmWalletError::Unauthorized);
+            return Ok(());
+        }
+        
+        let multisig = multisig.ok_or(error!(MmWalletError::MultisigRequired))?;
+        let proposal = proposal.ok_or(error!(MmWalletError::MultisigRequired))?;
+        multisig.owner_index(signer)?;
+        
+        proposal.execute(multisig, action)
+    }
+    
+    /// Check if `key` is the wallet's guardian (never true when none is set)
+    pub fn is_guardian(&self, key: &Pubkey) -> bool {
+        self.guardian != Pubkey::default() && *key == self.guardian
+    }
+    
+    /// Lamports the owner may withdraw from `balance` at `current_time`
+    /// (above the rent reserve and the unvested principal)
+    pub fn withdrawable(&self, balance: u64, current_time: i64) -> u64 {
+        balance
+            .saturating_sub(MIN_RENT_RESERVE)
+            .saturating_sub(self.vesting.locked(current_time))
+    }
+    
+    /// Lamports above the rent reserve and the locked principal, withdrawable any time
+    pub fn withdrawable_profit(&self, balance: u64) -> u64 {
+        balance
+            .saturating_sub(MIN_RENT_RESERVE)
+            .saturating_sub(self.principal)
+    }
+    
+    /// `withdrawable_profit` less claimed fees still owed to the fee split recipients
+    pub fn withdrawable_profit_after_fees(&self, balance: u64, fee_split: Option<&FeeSplit>) -> u64 {
+        let owed = fee_split.map_or(0, |split| split.undistributed(self.total_fees_claimed));
+        self.withdrawable_profit(balance).saturating_sub(owed)
+    }
+    
+    /// Tokens the owner may withdraw from `token_balance` at `current_time`
+    /// (the token lock only; `lock_until` is checked separately)
+    pub fn withdrawable_tokens(&self, token_balance: u64, current_time: i64) -> u64 {
+        token_balance.saturating_sub(self.token_vesting.locked(current_time))
+    }
     
-    /// Check if caller is authorized to execute trades
-    pub fn is_authorized(&self, caller: &Pubkey) -> bool {
-        *caller == self.owner || *caller == self.operator
+    /// Whether the liquidity commitment caps sells at `current_time`
+    pub fn commitment_active(&self, current_time: i64) -> bool {
+        self.commitment_bps > 0 && self.is_locked(current_time)
     }
     
     /// Check if wallet is currently locked
@@ -211,8 +728,18 @@ impl MmWallet {
     
     /// Calculate minimum output with slippage protection
     pub fn calculate_min_output(&self, expected_output: u64) -> Result<u64> {
+        self.calculate_min_output_capped(expected_output, 0)
+    }
+    
+    /// Calculate minimum output using the tighter of the wallet's slippage and
+    /// `max_slippage_bps` (0 = wallet setting)
+    pub fn calculate_min_output_capped(&self, expected_output: u64, max_slippage_bps: u16) -> Result<u64> {
+        let slippage_bps = match max_slippage_bps {
+            0 => self.config.slippage_bps,
+            cap => self.config.slippage_bps.min(cap),
+        };
         let slippage_factor = 10000u64
-            .checked_sub(self.config.slippage_bps as u64)
+            .checked_sub(slippage_bps as u64)
             .ok_or(error!(MmWalletError::MathOverflow))?;
         
         expected_output
@@ -222,6 +749,39 @@ impl MmWallet {
             .ok_or(error!(MmWalletError::MathOverflow))
     }
     
+    /// Effective quote tolerance (falls back to the default when unset)
+    pub fn quote_tolerance_bps(&self) -> u16 {
+        if self.config.quote_tolerance_bps == 0 {
+            DEFAULT_QUOTE_TOLERANCE_BPS
+        } else {
+            self.config.quote_tolerance_bps
+        }
+    }
+    
+    /// Ensure caller's expected output is within tolerance of the on-chain quote
+    pub fn check_quote_deviation(&self, expected_output: u64, quoted_output: u64) -> Result<()> {
+        let deviation = (expected_output as u128).abs_diff(quoted_output as u128);
+        let allowed = (quoted_output as u128)
+            .checked_mul(self.quote_tolerance_bps() as u128)
+            .ok_or(error!(MmWalletError::MathOverflow))?;
+        
+        require!(
+            deviation.checked_mul(10000).ok_or(error!(MmWalletError::MathOverflow))? <= allowed,
+            MmWalletError::QuoteDeviationTooHigh
+        );
+        
+        Ok(())
+    }
+    
+    /// Slippage floor for a quoted trade
+    /// 
+    /// The caller's `expected_output` must be within tolerance of the on-chain quote, but
+    /// the floor is applied to the quote itself so a low expectation can't loosen it.
+    pub fn min_output_from_quote(&self, expected_output: u64, quoted_output: u64, max_slippage_bps: u16) -> Result<u64> {
+        self.check_quote_deviation(expected_output, quoted_output)?;
+        self.calculate_min_output_capped(quoted_output, max_slippage_bps)
+    }
+    
     /// Check if enough time has passed since last trade
     pub fn can_trade(&self, current_time: i64) -> bool {
         if self.last_trade == 0 {
@@ -229,28 +789,177 @@ impl MmWallet {
         }
         current_time >= self.last_trade + (self.config.min_delay_secs as i64)
     }
-}
-
-impl Default for MmWallet {
-    fn default() -> Self {
-        Self {
-            version: PROGRAM_VERSION,
-            bump: 0,
-            owner: Pubkey::default(),
-            operator: Pubkey::default(),
-            token_mint: Pubkey::default(),
-            nonce: 0,
-            strategy: Strategy::default(),
-            config: StrategyConfig::default(),
-            lock_until: 0,
-            paused: false,
-            is_creator: false,
-            total_volume: 0,
-            total_trades: 0,
-            total_fees_claimed: 0,
+    
+    /// State and rate-limit checks shared by every trade instruction
+    pub fn check_trade_allowed(&self, current_time: i64) -> Result<()> {
+        // ═══ STATE CHECKS ═══
+        require!(!self.paused, MmWalletError::TradingPaused);
+        require!(
+            self.token_mint != Pubkey::default(),
+            MmWalletError::TokenNotCreated
+        );
+        
+        // ═══ RATE LIMITING ═══
+        require!(self.can_trade(current_time), MmWalletError::TradeTooSoon);
+        
+        Ok(())
+    }
+    
+    /// Validate a buy's SOL input against the available balance
+    pub fn check_buy_amount(&self, amount: u64, available: u64) -> Result<()> {
+        let max_trade = self.max_trade_amount(available)?;
+        require!(amount <= max_trade, MmWalletError::TradeExceedsMax);
+        require!(amount <= available, MmWalletError::InsufficientBalance);
+        
+        Ok(())
+    }
+    
+    /// Validate a sell's token input against the PDA's token balance
+    pub fn check_sell_amount(&self, amount: u64, token_balance: u64) -> Result<()> {
+        let max_trade = self.max_trade_amount(token_balance)?;
+        require!(amount <= max_trade, MmWalletError::TradeExceedsMax);
+        require!(amount <= token_balance, MmWalletError::InsufficientBalance);
+        
+        Ok(())
+    }
+    
+    /// Check a sell left at least the token lock's locked amount in the PDA
+    pub fn check_token_lock(&self, token_balance: u64, current_time: i64) -> Result<()> {
+        require!(
+            token_balance >= self.token_vesting.locked(current_time),
+            MmWalletError::TokensLocked
+        );
+        
+        Ok(())
+    }
+    
+    /// Record a measured fill in the wallet's trade stats
+    pub fn record_fill(&mut self, fill: &Fill, current_time: i64) {
+        let sol = fill.sol_amount();
+        let tokens = fill.token_amount();
+        
+        match fill.side {
+            TradeSide::Buy => {
+                self.sol_in = self.sol_in.saturating_add(sol);
+                self.tokens_bought = self.tokens_bought.saturating_add(tokens);
+                self.token_inventory = self.token_inventory.saturating_add(tokens);
+                self.cost_basis = self.cost_basis.saturating_add(sol);
+            }
+            TradeSide::Sell => {
+                self.sol_out = self.sol_out.saturating_add(sol);
+                self.tokens_sold = self.tokens_sold.saturating_add(tokens);
+                
+                // Tokens sold beyond tracked inventory carry no cost basis
+                let cost_removed = if tokens >= self.token_inventory {
+                    self.cost_basis
+                } else {
+                    ((self.cost_basis as u128) * (tokens as u128) / (self.token_inventory as u128)) as u64
+                };
+                self.token_inventory = self.token_inventory.saturating_sub(tokens);
+                self.cost_basis = self.cost_basis.saturating_sub(cost_removed);
+                
+                let pnl = (sol as i128) - (cost_removed as i128);
+                self.realized_pnl = (self.realized_pnl as i128)
+                    .saturating_add(pnl)
+                    .clamp(i64::MIN as i128, i64::MAX as i128) as i64;
+            }
+        }
+        
+        self.total_volume = self.total_volume.saturating_add(sol);
+        self.total_trades = self.total_trades.saturating_add(1);
+        self.last_trade = current_time;
+    }
+    
+    /// Part of `total_fees_claimed` collected from the Pump.fun bonding curve
+    pub fn curve_fees_claimed(&self) -> u64 {
+        self.total_fees_claimed.saturating_sub(self.swap_fees_claimed)
+    }
+    
+    /// Average entry price of the tracked inventory (lamports per token, scaled by PRICE_SCALE)
+    pub fn avg_entry_price(&self) -> u64 {
+        if self.token_inventory == 0 {
+            return 0;
+        }
+        ((self.cost_basis as u128) * PRICE_SCALE / (self.token_inventory as u128))
+            .min(u64::MAX as u128) as u64
+    }
+    
+    /// Upgrade a v2 account that has been zero-extended to `SIZE`
+    /// 
+    /// v2 seeds were derived from `owner`, which could not change, so it becomes `seed_owner`.
+    /// Deposits were not tracked in v2, so the whole `balance` becomes principal.
+    /// Other fields added since read as zero, which is their default.
+    pub fn migrate_data(data: &mut [u8], balance: u64) -> Result<()> {
+        let mut wallet = MmWallet::try_deserialize(&mut &data[..])?;
+        
+        if wallet.version < 3 {
+            wallet.seed_owner = wallet.owner;
+            wallet.pending_owner = Pubkey::default();
+            wallet.principal = balance.saturating_sub(MIN_RENT_RESERVE);
+        }
+        wallet.version = PROGRAM_VERSION;
+        
+        wallet.try_serialize(&mut &mut data[..])
+    }
+    
+    /// Invoke a CPI with the wallet PDA as signer
+    pub fn invoke_signed(&self, ix: &Instruction, accounts: &[AccountInfo]) -> Result<()> {
+        let nonce_bytes = self.nonce.to_le_bytes();
+        let seeds = &[
+            b"mm_wallet",
+            self.seed_owner.as_ref(),
+            nonce_bytes.as_ref(),
+            &[self.bump],
+        ];
+        
+        invoke_signed(ix, accounts, &[&seeds[..]])?;
+        
+        Ok(())
+    }
+}
+
+impl Default for MmWallet {
+    fn default() -> Self {
+        Self {
+            version: PROGRAM_VERSION,
+            bump: 0,
+            owner: Pubkey::default(),
+            operator: Pubkey::default(),
+            token_mint: Pubkey::default(),
+            nonce: 0,
+            strategy: Strategy::default(),
+            config: StrategyConfig::default(),
+            lock_until: 0,
+            paused: false,
+            is_creator: false,
+            total_volume: 0,
+            total_trades: 0,
+            total_fees_claimed: 0,
             last_trade: 0,
             created_at: 0,
-            reserved: [0u8; 64],
+            sol_in: 0,
+            sol_out: 0,
+            tokens_bought: 0,
+            tokens_sold: 0,
+            token_inventory: 0,
+            cost_basis: 0,
+            realized_pnl: 0,
+            seed_owner: Pubkey::default(),
+            pending_owner: Pubkey::default(),
+            guardian: Pubkey::default(),
+            fee_tier: 0,
+            total_protocol_fees: 0,
+            swap_fees_claimed: 0,
+            vesting: VestingSchedule::default(),
+            principal: 0,
+            profits_withdrawn: 0,
+            token_vesting: VestingSchedule::default(),
+            commitment_bps: 0,
+            multisig: false,
+            risk_limits: false,
+            fee_split: false,
+            operator_registry: false,
+            reserved: [0u8; 1],
         }
     }
 }
@@ -268,8 +977,54 @@ pub enum Strategy {
     PumpHunter = 5,
 }
 
+/// Trade direction
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[repr(u8)]
+pub enum TradeSide {
+    #[default]
+    Buy = 0,
+    Sell = 1,
+}
+
+impl TradeSide {
+    /// Operator role required to trade this side
+    pub fn role(self) -> u8 {
+        match self {
+            TradeSide::Buy => ROLE_BUY,
+            TradeSide::Sell => ROLE_SELL,
+        }
+    }
+}
+
+/// Venue a trade was executed on
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[repr(u8)]
+pub enum Venue {
+    #[default]
+    BondingCurve = 0,
+    PumpSwap = 1,
+}
+
+impl Venue {
+    /// Program the venue's trades CPI into
+    pub fn program_id(self) -> Pubkey {
+        match self {
+            Venue::BondingCurve => PUMP_FUN_PROGRAM,
+            Venue::PumpSwap => PUMPSWAP_PROGRAM,
+        }
+    }
+    
+    /// Extra operator role required to trade on this venue
+    pub fn role(self) -> u8 {
+        match self {
+            Venue::BondingCurve => 0,
+            Venue::PumpSwap => ROLE_SWAP,
+        }
+    }
+}
+
 /// Strategy configuration
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq)]
 pub struct StrategyConfig {
     /// Trade size as percentage of balance (1-50)
     pub trade_size_pct: u8,
@@ -292,8 +1047,11 @@ pub struct StrategyConfig {
     /// Strategy-specific parameter 3
     pub param3: u16,
     
+    /// Max deviation of expected output from the on-chain quote in bps (0 = default)
+    pub quote_tolerance_bps: u16,
+    
     /// Reserved for future parameters
-    pub reserved: [u8; 32],
+    pub reserved: [u8; 30],
 }
 
 // ═══════════════════════════════════════════════════════════════════════════════
@@ -370,6 +1128,210 @@ pub enum MmWalletError {
     
     #[msg("Operator cannot be zero address")]
     InvalidOperator,
+    
+    #[msg("Token account is not owned by the wallet PDA")]
+    InvalidTokenAccount,
+    
+    #[msg("Realized trade input exceeds the authorized amount")]
+    RealizedInputExceedsMax,
+    
+    #[msg("Realized trade output below slippage floor")]
+    RealizedOutputBelowMin,
+    
+    #[msg("Invalid Pump.fun bonding curve account")]
+    InvalidBondingCurve,
+    
+    #[msg("Invalid Pump.fun global account")]
+    InvalidPumpGlobal,
+    
+    #[msg("Bonding curve is complete - token has migrated to PumpSwap")]
+    BondingCurveComplete,
+    
+    #[msg("Expected output deviates too far from on-chain quote")]
+    QuoteDeviationTooHigh,
+    
+    #[msg("Invalid quote tolerance (must be 0-2000 bps)")]
+    InvalidQuoteTolerance,
+    
+    #[msg("Remaining accounts do not match the venue's expected layout")]
+    InvalidRemainingAccounts,
+    
+    #[msg("Invalid PumpSwap pool or global config account")]
+    InvalidPool,
+    
+    #[msg("Accounts required by the routed venue were not provided")]
+    MissingVenueAccounts,
+    
+    #[msg("Trade notional exceeds the per-trade lamport cap")]
+    TradeNotionalExceedsCap,
+    
+    #[msg("Rolling 24h buy notional cap exceeded")]
+    DailyBuyCapExceeded,
+    
+    #[msg("Rolling 24h sell notional cap exceeded")]
+    DailySellCapExceeded,
+    
+    #[msg("Token position would exceed the configured maximum")]
+    PositionLimitExceeded,
+    
+    #[msg("Sell amount exceeds the per-trade token cap")]
+    SellExceedsCap,
+    
+    #[msg("Invalid drawdown threshold (must be 0-10000 bps)")]
+    InvalidDrawdownThreshold,
+    
+    #[msg("No ownership transfer pending for this signer")]
+    NoPendingOwner,
+    
+    #[msg("Account is not an MM wallet owned by this program")]
+    InvalidWalletAccount,
+    
+    #[msg("Wallet already uses the current account layout")]
+    WalletAlreadyMigrated,
+    
+    #[msg("Invalid operator roles")]
+    InvalidRoles,
+    
+    #[msg("Caller lacks the role required for this action")]
+    MissingRole,
+    
+    #[msg("Operator is already registered")]
+    OperatorAlreadyExists,
+    
+    #[msg("Operator is not registered")]
+    OperatorNotFound,
+    
+    #[msg("Operator registry is full")]
+    OperatorRegistryFull,
+    
+    #[msg("Trade exceeds the operator's size ceiling")]
+    OperatorTradeCapExceeded,
+    
+    #[msg("Session expiry must be set and in the future")]
+    InvalidSessionExpiry,
+    
+    #[msg("Session key has expired")]
+    SessionExpired,
+    
+    #[msg("Session key has been revoked")]
+    SessionRevoked,
+    
+    #[msg("Trade exceeds the session key's remaining allowance")]
+    SessionAllowanceExceeded,
+    
+    #[msg("Protocol is paused")]
+    ProtocolPaused,
+    
+    #[msg("External program is not on the protocol allowlist")]
+    ProgramNotAllowed,
+    
+    #[msg("Invalid protocol config bounds")]
+    InvalidProtocolBounds,
+    
+    #[msg("Too many allowed programs")]
+    TooManyAllowedPrograms,
+    
+    #[msg("Protocol fee exceeds maximum")]
+    ProtocolFeeTooHigh,
+    
+    #[msg("Invalid protocol treasury")]
+    InvalidTreasury,
+    
+    #[msg("Invalid protocol fee tier")]
+    InvalidFeeTier,
+    
+    #[msg("Fee split must have 1-8 unique recipients with weights summing to 10000 bps")]
+    InvalidFeeSplit,
+    
+    #[msg("No claimed fees to distribute")]
+    NothingToDistribute,
+    
+    #[msg("Invalid vesting schedule")]
+    InvalidVestingSchedule,
+    
+    #[msg("Amount exceeds the vested balance")]
+    AmountNotVested,
+    
+    #[msg("Amount exceeds the profit above locked principal")]
+    InsufficientProfit,
+    
+    #[msg("Amount exceeds the unlocked token balance")]
+    TokensLocked,
+    
+    #[msg("Token lock is still running")]
+    TokenLockActive,
+    
+    #[msg("Invalid liquidity commitment")]
+    InvalidCommitment,
+    
+    #[msg("Net sells exceed the liquidity commitment")]
+    CommitmentSellCapExceeded,
+    
+    #[msg("Wallet is locked: queue the change with queue_config_change")]
+    ConfigChangeTimelocked,
+    
+    #[msg("Config change delay has not elapsed")]
+    ConfigChangeNotReady,
+    
+    #[msg("Operator registry account required for this wallet")]
+    OperatorRegistryRequired,
+    
+    #[msg("Wallet has multisig ownership: an approved proposal is required")]
+    MultisigRequired,
+    
+    #[msg("Invalid multisig owners or threshold")]
+    InvalidMultisig,
+    
+    #[msg("Signer is not a multisig owner")]
+    NotMultisigOwner,
+    
+    #[msg("Owner already approved this proposal")]
+    AlreadyApproved,
+    
+    #[msg("Proposal has not reached the approval threshold")]
+    ProposalNotApproved,
+    
+    #[msg("Proposal does not match this action")]
+    ProposalMismatch,
+    
+    #[msg("Proposal already executed")]
+    ProposalAlreadyExecuted,
+    
+    #[msg("Proposal predates the current multisig owner set")]
+    ProposalStale,
+    
+    #[msg("Invalid recovery guardians, threshold or challenge period")]
+    InvalidRecoveryConfig,
+    
+    #[msg("Signer is not a recovery guardian")]
+    NotRecoveryGuardian,
+    
+    #[msg("A recovery is already in progress")]
+    RecoveryInProgress,
+    
+    #[msg("No recovery in progress")]
+    NoRecoveryInProgress,
+    
+    #[msg("Guardian already supports this recovery")]
+    RecoveryAlreadySupported,
+    
+    #[msg("Recovery lacks guardian support or is still in its challenge period")]
+    RecoveryNotReady,
+    
+    #[msg("Risk limits account required for this wallet")]
+    RiskLimitsRequired,
+    
+    #[msg("Treasury WSOL account required for PumpSwap sells")]
+    TreasuryQuoteAccountRequired,
+    
+    #[msg("Fee split account required for this wallet")]
+    FeeSplitRequired,
+    
+    #[msg("Session account required for this change")]
+    SessionAccountRequired,
+    
+    #[msg("Fee split has undistributed fees: call distribute_fees first")]
+    FeesPendingDistribution,
 }
 
 // ═══════════════════════════════════════════════════════════════════════════════
@@ -392,6 +1354,10 @@ pub mod mm_wallet_v2 {
     /// * `strategy` - Initial trading strategy
     /// * `config` - Strategy configuration
     /// * `operator` - Authorized address for trade execution
+    /// * `vesting` - Optional linear unlock of SOL principal, on top of the `lock_seconds` cliff
+    /// * `commitment_bps` - Liquidity commitment: max net sells per 24h while locked, in bps
+    ///   of inventory (0 = none; requires a lock and can't be changed until it ends)
+    #[allow(clippy::too_many_arguments, clippy::manual_range_contains)]
     pub fn initialize(
         ctx: Context<Initialize>,
         nonce: u64,
@@ -399,6 +1365,8 @@ pub mod mm_wallet_v2 {
         strategy: Strategy,
         config: StrategyConfig,
         operator: Pubkey,
+        vesting: Option<VestingParams>,
+        commitment_bps: u16,
     ) -> Result<()> {
         // Validate lock duration
         require!(
@@ -406,6 +1374,12 @@ pub mod mm_wallet_v2 {
             MmWalletError::InvalidLockDuration
         );
         
+        // Validate liquidity commitment
+        require!(
+            commitment_bps <= 10_000 && (commitment_bps == 0 || lock_seconds > 0),
+            MmWalletError::InvalidCommitment
+        );
+        
         // Validate operator is not zero
         require!(
             operator != Pubkey::default(),
@@ -413,7 +1387,8 @@ pub mod mm_wallet_v2 {
         );
         
         // Validate config
-        validate_config(&config)?;
+        let bounds = ctx.accounts.protocol_config.as_ref().map_or(ConfigBounds::DEFAULT, |p| p.bounds);
+        validate_config(&config, &bounds)?;
         
         let clock = Clock::get()?;
         let wallet_key = ctx.accounts.mm_wallet.key();
@@ -444,7 +1419,32 @@ pub mod mm_wallet_v2 {
         wallet.total_fees_claimed = 0;
         wallet.last_trade = 0;
         wallet.created_at = clock.unix_timestamp;
-        wallet.reserved = [0u8; 64];
+        wallet.sol_in = 0;
+        wallet.sol_out = 0;
+        wallet.tokens_bought = 0;
+        wallet.tokens_sold = 0;
+        wallet.token_inventory = 0;
+        wallet.cost_basis = 0;
+        wallet.realized_pnl = 0;
+        wallet.seed_owner = owner_key;
+        wallet.pending_owner = Pubkey::default();
+        wallet.guardian = Pubkey::default();
+        wallet.fee_tier = 0;
+        wallet.total_protocol_fees = 0;
+        wallet.swap_fees_claimed = 0;
+        wallet.vesting = match vesting {
+            Some(params) => params.schedule(clock.unix_timestamp)?,
+            None => VestingSchedule::default(),
+        };
+        wallet.principal = 0;
+        wallet.profits_withdrawn = 0;
+        wallet.token_vesting = VestingSchedule::default();
+        wallet.commitment_bps = commitment_bps;
+        wallet.multisig = false;
+        wallet.risk_limits = false;
+        wallet.fee_split = false;
+        wallet.operator_registry = false;
+        wallet.reserved = [0u8; 1];
         
         let lock_until_val = wallet.lock_until;
         
@@ -454,12 +1454,17 @@ pub mod mm_wallet_v2 {
             wallet: wallet_key,
             lock_until: lock_until_val,
             strategy: strategy as u8,
+            commitment_bps,
         });
         
         msg!("MM Wallet v{} initialized for owner: {}", PROGRAM_VERSION, owner_key);
         msg!("Operator: {}", wallet.operator);
         msg!("Lock until: {}", lock_until_val);
         
+        if wallet.vesting.is_set() {
+            emit_vesting_updated(wallet_key, &wallet.vesting);
+        }
+        
         Ok(())
     }
 
@@ -488,6 +1493,9 @@ pub mod mm_wallet_v2 {
             ],
         )?;
         
+        let wallet = &mut ctx.accounts.mm_wallet;
+        wallet.principal = wallet.principal.saturating_add(amount);
+        
         // Emit event
         emit!(Deposited {
             wallet: ctx.accounts.mm_wallet.key(),
@@ -505,15 +1513,21 @@ pub mod mm_wallet_v2 {
     // ═══════════════════════════════════════════════════════════════════════════
 
     /// Withdraw SOL from the MM wallet (owner only, after lock expires)
+    /// 
+    /// Profit goes out before principal. Claimed fees owed to the fee split stay for
+    /// `distribute_fees`.
     pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
         let wallet = &ctx.accounts.mm_wallet;
         let clock = Clock::get()?;
         
-        // Check ownership
-        require!(
-            ctx.accounts.owner.key() == wallet.owner,
-            MmWalletError::Unauthorized
-        );
+        // Check ownership (or an approved multisig proposal)
+        check_owner_action(
+            wallet,
+            &ctx.accounts.owner,
+            &ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            ProposalAction::Withdraw { amount },
+        )?;
         
         // Check lock
         require!(
@@ -534,10 +1548,35 @@ pub mod mm_wallet_v2 {
         let max_withdraw = pda_balance.saturating_sub(MIN_RENT_RESERVE);
         require!(amount <= max_withdraw, MmWalletError::BelowRentReserve);
         
+        // Ensure unvested principal remains
+        require!(
+            amount <= wallet.withdrawable(pda_balance, clock.unix_timestamp),
+            MmWalletError::AmountNotVested
+        );
+        
+        // ... and fees owed to the fee split
+        require!(
+            ctx.accounts.fee_split.is_some() || !wallet.fee_split,
+            MmWalletError::FeeSplitRequired
+        );
+        let fee_split = ctx.accounts.fee_split.as_deref();
+        let owed = fee_split.map_or(0, |split| split.undistributed(wallet.total_fees_claimed));
+        require!(
+            amount <= wallet.withdrawable(pda_balance, clock.unix_timestamp).saturating_sub(owed),
+            MmWalletError::FeesPendingDistribution
+        );
+        
+        // Profit goes out first, then principal
+        let from_principal = amount.saturating_sub(wallet.withdrawable_profit_after_fees(pda_balance, fee_split));
+        
         // Transfer from PDA to owner
         **ctx.accounts.pda_wallet.try_borrow_mut_lamports()? -= amount;
         **ctx.accounts.destination.try_borrow_mut_lamports()? += amount;
         
+        let wallet = &mut ctx.accounts.mm_wallet;
+        wallet.principal = wallet.principal.saturating_sub(from_principal);
+        record_withdrawal(wallet, ctx.accounts.risk_limits.as_mut(), amount)?;
+        
         // Emit event
         emit!(Withdrawn {
             wallet: ctx.accounts.mm_wallet.key(),
@@ -550,16 +1589,80 @@ pub mod mm_wallet_v2 {
         Ok(())
     }
 
-    /// Withdraw all tokens from the MM wallet (owner only, after lock expires)
-    pub fn withdraw_tokens(ctx: Context<WithdrawTokens>) -> Result<()> {
+    /// Withdraw claimed fees and realized profit, leaving the locked principal in place
+    /// 
+    /// Allowed while the wallet is locked: only SOL above `principal` and the rent
+    /// reserve can leave. Claimed fees owed to the fee split stay for `distribute_fees`.
+    pub fn withdraw_profits(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
         let wallet = &ctx.accounts.mm_wallet;
-        let clock = Clock::get()?;
         
-        // Check ownership
+        // Check ownership (or an approved multisig proposal)
+        check_owner_action(
+            wallet,
+            &ctx.accounts.owner,
+            &ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            ProposalAction::WithdrawProfits { amount },
+        )?;
+        
+        // Check destination is owner (prevent accidental sends)
         require!(
-            ctx.accounts.owner.key() == wallet.owner,
-            MmWalletError::Unauthorized
+            ctx.accounts.destination.key() == wallet.owner,
+            MmWalletError::InvalidWithdrawDestination
+        );
+        
+        require!(amount > 0, MmWalletError::InsufficientProfit);
+        
+        // Never dip into principal, the rent reserve or fees owed to the fee split
+        require!(
+            ctx.accounts.fee_split.is_some() || !wallet.fee_split,
+            MmWalletError::FeeSplitRequired
+        );
+        let available = wallet.withdrawable_profit_after_fees(
+            ctx.accounts.pda_wallet.lamports(),
+            ctx.accounts.fee_split.as_deref(),
         );
+        require!(amount <= available, MmWalletError::InsufficientProfit);
+        
+        // Transfer from PDA to owner
+        **ctx.accounts.pda_wallet.try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.destination.try_borrow_mut_lamports()? += amount;
+        
+        let wallet = &mut ctx.accounts.mm_wallet;
+        wallet.profits_withdrawn = wallet.profits_withdrawn.saturating_add(amount);
+        record_withdrawal(wallet, ctx.accounts.risk_limits.as_mut(), amount)?;
+        
+        // Emit event
+        emit!(ProfitsWithdrawn {
+            wallet: wallet.key(),
+            owner: ctx.accounts.owner.key(),
+            amount,
+            principal: wallet.principal,
+        });
+        
+        msg!("Withdrawn {} lamports of profit to owner", amount);
+        
+        Ok(())
+    }
+
+    /// Withdraw tokens from the MM wallet (owner only, after lock expires)
+    /// 
+    /// `amount` of 0 withdraws everything the token lock allows.
+    pub fn withdraw_tokens(ctx: Context<WithdrawTokens>, amount: u64) -> Result<()> {
+        let wallet = &ctx.accounts.mm_wallet;
+        let clock = Clock::get()?;
+        
+        // Check ownership (or an approved multisig proposal)
+        check_owner_action(
+            wallet,
+            &ctx.accounts.owner,
+            &ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            ProposalAction::WithdrawTokens {
+                amount,
+                destination: ctx.accounts.owner_token_account.key(),
+            },
+        )?;
         
         // Check lock
         require!(
@@ -573,7 +1676,11 @@ pub mod mm_wallet_v2 {
             MmWalletError::TokenMintMismatch
         );
         
-        let amount = ctx.accounts.pda_token_account.amount;
+        // Check token lock
+        let balance = ctx.accounts.pda_token_account.amount;
+        let withdrawable = wallet.withdrawable_tokens(balance, clock.unix_timestamp);
+        let amount = if amount == 0 { withdrawable } else { amount };
+        require!(amount <= withdrawable, MmWalletError::TokensLocked);
         
         if amount == 0 {
             msg!("No tokens to withdraw");
@@ -581,11 +1688,11 @@ pub mod mm_wallet_v2 {
         }
         
         // Build signer seeds for PDA
-        let owner_key = wallet.owner;
+        let seed_owner = wallet.seed_owner;
         let nonce_bytes = wallet.nonce.to_le_bytes();
         let seeds = &[
             b"mm_wallet",
-            owner_key.as_ref(),
+            seed_owner.as_ref(),
             nonce_bytes.as_ref(),
             &[wallet.bump],
         ];
@@ -602,159 +1709,358 @@ pub mod mm_wallet_v2 {
         
         token::transfer(cpi_ctx, amount)?;
         
+        // Emit event
+        emit!(TokensWithdrawn {
+            wallet: wallet.key(),
+            owner: ctx.accounts.owner.key(),
+            mint: wallet.token_mint,
+            amount,
+            remaining: balance - amount,
+            locked: wallet.token_vesting.locked(clock.unix_timestamp),
+        });
+        
         msg!("Withdrawn {} tokens to owner", amount);
         
         Ok(())
     }
 
-    // ═══════════════════════════════════════════════════════════════════════════
-    // TRADING OPERATIONS (Authorized operator only)
-    // ═══════════════════════════════════════════════════════════════════════════
-
-    /// Execute a buy on Pump.fun bonding curve
+    /// Lock the PDA's tokens on their own schedule (owner only)
     /// 
-    /// # Security
-    /// - Validates caller is owner or authorized operator
-    /// - Validates target program is Pump.fun
-    /// - Enforces trade amount limits
-    /// - Enforces rate limiting
-    /// - Calculates slippage protection on-chain
-    pub fn execute_buy(
-        ctx: Context<ExecuteTrade>,
-        amount_lamports: u64,
-        expected_tokens: u64, // Expected output from off-chain calculation
-    ) -> Result<()> {
+    /// A plain cliff has `cliff_seconds == duration_seconds`. Can't replace a lock that
+    /// is still running; use `extend_token_lock` to lengthen it.
+    pub fn set_token_lock(ctx: Context<OwnerAction>, params: VestingParams) -> Result<()> {
         let wallet = &mut ctx.accounts.mm_wallet;
         let clock = Clock::get()?;
         
-        // ═══ AUTHORIZATION ═══
-        require!(
-            wallet.is_authorized(&ctx.accounts.caller.key()),
-            MmWalletError::UnauthorizedOperator
-        );
-        
-        // ═══ STATE CHECKS ═══
-        require!(!wallet.paused, MmWalletError::TradingPaused);
+        // Check ownership (or an approved multisig proposal)
+        check_owner_action(
+            wallet,
+            &ctx.accounts.owner,
+            &ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            ProposalAction::SetTokenLock { params },
+        )?;
         
-        // ═══ RATE LIMITING ═══
         require!(
-            wallet.can_trade(clock.unix_timestamp),
-            MmWalletError::TradeTooSoon
+            wallet.token_vesting.locked(clock.unix_timestamp) == 0,
+            MmWalletError::TokenLockActive
         );
         
-        // ═══ BALANCE & AMOUNT VALIDATION ═══
-        let pda_balance = ctx.accounts.pda_wallet.lamports();
-        let available = pda_balance.saturating_sub(MIN_RENT_RESERVE);
+        wallet.token_vesting = params.schedule(clock.unix_timestamp)?;
         
-        let max_trade = wallet.max_trade_amount(available)?;
-        require!(amount_lamports <= max_trade, MmWalletError::TradeExceedsMax);
-        require!(amount_lamports <= available, MmWalletError::InsufficientBalance);
+        emit_token_lock_updated(wallet.key(), &wallet.token_vesting);
         
-        // ═══ PROGRAM VALIDATION ═══
-        require!(
-            ctx.accounts.target_program.key() == PUMP_FUN_PROGRAM,
-            MmWalletError::InvalidProgram
-        );
+        msg!("Tokens locked until: {}", wallet.token_vesting.end);
         
-        // ═══ SLIPPAGE PROTECTION (calculated on-chain) ═══
-        let min_tokens_out = wallet.calculate_min_output(expected_tokens)?;
+        Ok(())
+    }
+
+    /// Push back the end of a running token lock (can only increase, never decrease)
+    pub fn extend_token_lock(ctx: Context<OwnerAction>, additional_seconds: i64) -> Result<()> {
+        let wallet = &mut ctx.accounts.mm_wallet;
+        let clock = Clock::get()?;
         
-        // ═══ BUILD CPI ═══
-        let owner_key = wallet.owner;
-        let nonce_bytes = wallet.nonce.to_le_bytes();
-        let seeds = &[
-            b"mm_wallet",
-            owner_key.as_ref(),
-            nonce_bytes.as_ref(),
-            &[wallet.bump],
-        ];
-        let signer_seeds = &[&seeds[..]];
-        
-        let mut data = PUMP_BUY_DISCRIMINATOR.to_vec();
-        data.extend_from_slice(&min_tokens_out.to_le_bytes());
-        data.extend_from_slice(&amount_lamports.to_le_bytes());
+        // Check ownership (or an approved multisig proposal)
+        check_owner_action(
+            wallet,
+            &ctx.accounts.owner,
+            &ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            ProposalAction::ExtendTokenLock { additional_seconds },
+        )?;
         
-        let ix = anchor_lang::solana_program::instruction::Instruction {
-            program_id: PUMP_FUN_PROGRAM,
-            accounts: ctx.remaining_accounts.iter().map(|a| {
-                if a.is_writable {
-                    anchor_lang::solana_program::instruction::AccountMeta::new(*a.key, a.is_signer)
-                } else {
-                    anchor_lang::solana_program::instruction::AccountMeta::new_readonly(*a.key, a.is_signer)
-                }
-            }).collect(),
-            data,
-        };
+        // Validate additional time
+        require!(
+            additional_seconds > 0 && additional_seconds <= MAX_LOCK_SECONDS,
+            MmWalletError::InvalidLockDuration
+        );
+        require!(
+            wallet.token_vesting.end > clock.unix_timestamp,
+            MmWalletError::InvalidVestingSchedule
+        );
         
-        // Execute CPI with PDA as signer
-        invoke_signed(&ix, ctx.remaining_accounts, signer_seeds)?;
+        // Validate total lock doesn't exceed maximum (5 years from now)
+        let max_allowed = clock.unix_timestamp
+            .checked_add(MAX_TOTAL_LOCK_SECONDS)
+            .ok_or(MmWalletError::MathOverflow)?;
         
-        // ═══ UPDATE STATS ═══
-        wallet.total_volume = wallet.total_volume.saturating_add(amount_lamports);
-        wallet.total_trades = wallet.total_trades.saturating_add(1);
-        wallet.last_trade = clock.unix_timestamp;
+        wallet.token_vesting.extend(additional_seconds)?;
+        require!(wallet.token_vesting.end <= max_allowed, MmWalletError::LockTooLong);
         
-        // Emit event
-        emit!(TradeExecuted {
-            wallet: ctx.accounts.mm_wallet.key(),
-            trade_type: 0, // buy
-            amount_in: amount_lamports,
-            min_amount_out: min_tokens_out,
-            timestamp: clock.unix_timestamp,
-        });
+        emit_token_lock_updated(wallet.key(), &wallet.token_vesting);
         
-        msg!("Executed buy: {} lamports, min tokens: {}", amount_lamports, min_tokens_out);
+        msg!("Token lock extended to: {}", wallet.token_vesting.end);
         
         Ok(())
     }
 
+    // ═══════════════════════════════════════════════════════════════════════════
+    // TRADING OPERATIONS (Authorized operator only)
+    // ═══════════════════════════════════════════════════════════════════════════
+
+    /// Execute a buy on Pump.fun bonding curve
+    /// 
+    /// # Security
+    /// - Validates caller holds the buy role (owner, operator or registered operator)
+    /// - Validates target program is Pump.fun
+    /// - Binds CPI accounts to the wallet's token mint
+    /// - Enforces trade amount limits
+    /// - Enforces rate limiting
+    /// - Checks expected output against an on-chain bonding-curve quote
+    /// - Calculates slippage protection on-chain
+    /// - Charges the protocol fee to the configured treasury
+    pub fn execute_buy<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExecuteTrade<'info>>,
+        amount_lamports: u64,
+        expected_tokens: u64, // Expected output from off-chain calculation
+    ) -> Result<()> {
+        execute_bonding_curve_trade(ctx, TradeSide::Buy, amount_lamports, expected_tokens)
+    }
+
     /// Execute a sell on Pump.fun bonding curve
-    pub fn execute_sell(
-        ctx: Context<ExecuteTrade>,
+    pub fn execute_sell<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExecuteTrade<'info>>,
         token_amount: u64,
         expected_sol: u64, // Expected output from off-chain calculation
     ) -> Result<()> {
-        let wallet = &mut ctx.accounts.mm_wallet;
+        execute_bonding_curve_trade(ctx, TradeSide::Sell, token_amount, expected_sol)
+    }
+
+    /// Execute a swap on PumpSwap AMM (for migrated tokens)
+    /// 
+    /// Buys are exact-out: `buy(min_amount_out, amount_in)` spends at most `amount_in`
+    /// lamports of WSOL. Sells are exact-in: `sell(amount_in, min_amount_out)`.
+    /// SOL is settled through the PDA's WSOL account; buy shortfalls are wrapped from
+    /// the PDA's lamports before the CPI, and sell proceeds are unwrapped after it.
+    pub fn execute_swap<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExecuteSwap<'info>>,
+        amount_in: u64,
+        expected_out: u64,
+        is_buy: bool,
+    ) -> Result<()> {
         let clock = Clock::get()?;
+        let side = if is_buy { TradeSide::Buy } else { TradeSide::Sell };
         
         // ═══ AUTHORIZATION ═══
+        let permissions = caller_permissions(
+            &ctx.accounts.mm_wallet,
+            &ctx.accounts.caller.key(),
+            ctx.accounts.operator_registry.as_deref(),
+            ctx.accounts.session.as_deref(),
+            &clock,
+        )?;
+        permissions.require(side.role() | Venue::PumpSwap.role())?;
+        
+        ctx.accounts.mm_wallet.check_trade_allowed(clock.unix_timestamp)?;
+        
+        // ═══ PROGRAM VALIDATION ═══
         require!(
-            wallet.is_authorized(&ctx.accounts.caller.key()),
-            MmWalletError::UnauthorizedOperator
+            ctx.accounts.target_program.key() == PUMPSWAP_PROGRAM,
+            MmWalletError::InvalidProgram
         );
+        ctx.accounts.protocol_config.check_trade(&ctx.accounts.mm_wallet.config, &PUMPSWAP_PROGRAM)?;
+        
+        let accounts = &mut *ctx.accounts;
+        let fill = pumpswap::execute_trade(
+            SwapTradeAccounts {
+                pda_wallet: &accounts.pda_wallet,
+                pda_token_account: &mut accounts.pda_token_account,
+                pda_quote_account: &mut accounts.pda_quote_account,
+                pool: &accounts.pool,
+                pool_base_token_account: &accounts.pool_base_token_account,
+                pool_quote_token_account: &accounts.pool_quote_token_account,
+                global_config: &accounts.global_config,
+                token_program: &accounts.token_program,
+                remaining: ctx.remaining_accounts,
+            },
+            &accounts.mm_wallet,
+            side,
+            amount_in,
+            expected_out,
+            permissions.max_slippage_bps,
+        )?;
+        permissions.check_fill(&fill)?;
+        charge_session(accounts.mm_wallet.key(), accounts.session.as_mut(), &fill)?;
+        charge_protocol_fee(
+            &mut accounts.mm_wallet,
+            &accounts.protocol_config,
+            &accounts.pda_wallet,
+            &accounts.treasury,
+            Some(QuoteFeeAccounts {
+                pda_quote_account: &mut accounts.pda_quote_account,
+                treasury_quote_account: accounts.treasury_quote_account.as_ref(),
+                token_program: &accounts.token_program,
+            }),
+            &fill,
+            clock.unix_timestamp,
+        )?;
+        if side == TradeSide::Sell {
+            pumpswap::unwrap_quote(
+                &accounts.mm_wallet,
+                &accounts.pda_wallet,
+                &mut accounts.pda_quote_account,
+                &accounts.token_program,
+                ctx.remaining_accounts,
+            )?;
+        }
         
-        // ═══ STATE CHECKS ═══
-        require!(!wallet.paused, MmWalletError::TradingPaused);
+        let holdings = BalanceSnapshot::capture_holdings(
+            &accounts.pda_wallet,
+            &accounts.pda_token_account,
+            Some(&accounts.pda_quote_account),
+        );
+        settle_trade(&mut accounts.mm_wallet, accounts.risk_limits.as_mut(), &fill, holdings, clock.unix_timestamp)
+    }
+
+    /// Execute a trade on whichever venue currently lists the wallet's token
+    /// 
+    /// Routes to the Pump.fun bonding curve until it completes, then to PumpSwap,
+    /// with the same checks as `execute_buy`/`execute_sell`/`execute_swap`.
+    /// `limit` is the caller's expected output and is bounded by the on-chain quote;
+    /// the slippage floor is derived from it.
+    pub fn execute_trade<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExecuteRoutedTrade<'info>>,
+        side: TradeSide,
+        amount: u64,
+        limit: u64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
         
-        // ═══ RATE LIMITING ═══
-        require!(
-            wallet.can_trade(clock.unix_timestamp),
-            MmWalletError::TradeTooSoon
+        // ═══ AUTHORIZATION ═══
+        let permissions = caller_permissions(
+            &ctx.accounts.mm_wallet,
+            &ctx.accounts.caller.key(),
+            ctx.accounts.operator_registry.as_deref(),
+            ctx.accounts.session.as_deref(),
+            &clock,
+        )?;
+        
+        ctx.accounts.mm_wallet.check_trade_allowed(clock.unix_timestamp)?;
+        
+        // ═══ ROUTING ═══
+        require_keys_eq!(
+            ctx.accounts.bonding_curve.key(),
+            pump_fun::bonding_curve_address(&ctx.accounts.mm_wallet.token_mint),
+            MmWalletError::TokenMintMismatch
         );
+        let curve = BondingCurveState::try_from_account(&ctx.accounts.bonding_curve)?;
+        let venue = if curve.complete { Venue::PumpSwap } else { Venue::BondingCurve };
+        permissions.require(side.role() | venue.role())?;
+        ctx.accounts.protocol_config.check_trade(&ctx.accounts.mm_wallet.config, &venue.program_id())?;
+        
+        let accounts = &mut *ctx.accounts;
+        let fill = if venue == Venue::BondingCurve {
+            pump_fun::execute_trade(
+                CurveTradeAccounts {
+                    pda_wallet: &accounts.pda_wallet,
+                    pda_token_account: &mut accounts.pda_token_account,
+                    bonding_curve: &accounts.bonding_curve,
+                    pump_global: accounts.pump_global.as_ref().ok_or(MmWalletError::MissingVenueAccounts)?,
+                    remaining: ctx.remaining_accounts,
+                },
+                &accounts.mm_wallet,
+                side,
+                amount,
+                limit,
+                permissions.max_slippage_bps,
+            )?
+        } else {
+            let missing = || error!(MmWalletError::MissingVenueAccounts);
+            pumpswap::execute_trade(
+                SwapTradeAccounts {
+                    pda_wallet: &accounts.pda_wallet,
+                    pda_token_account: &mut accounts.pda_token_account,
+                    pda_quote_account: accounts.pda_quote_account.as_mut().ok_or_else(missing)?,
+                    pool: accounts.pool.as_ref().ok_or_else(missing)?,
+                    pool_base_token_account: accounts.pool_base_token_account.as_ref().ok_or_else(missing)?,
+                    pool_quote_token_account: accounts.pool_quote_token_account.as_ref().ok_or_else(missing)?,
+                    global_config: accounts.global_config.as_ref().ok_or_else(missing)?,
+                    token_program: &accounts.token_program,
+                    remaining: ctx.remaining_accounts,
+                },
+                &accounts.mm_wallet,
+                side,
+                amount,
+                limit,
+                permissions.max_slippage_bps,
+            )?
+        };
+        permissions.check_fill(&fill)?;
+        charge_session(accounts.mm_wallet.key(), accounts.session.as_mut(), &fill)?;
+        charge_protocol_fee(
+            &mut accounts.mm_wallet,
+            &accounts.protocol_config,
+            &accounts.pda_wallet,
+            &accounts.treasury,
+            accounts.pda_quote_account.as_mut().map(|pda_quote_account| QuoteFeeAccounts {
+                pda_quote_account,
+                treasury_quote_account: accounts.treasury_quote_account.as_ref(),
+                token_program: &accounts.token_program,
+            }),
+            &fill,
+            clock.unix_timestamp,
+        )?;
+        if venue == Venue::PumpSwap && side == TradeSide::Sell {
+            if let Some(pda_quote_account) = accounts.pda_quote_account.as_mut() {
+                pumpswap::unwrap_quote(
+                    &accounts.mm_wallet,
+                    &accounts.pda_wallet,
+                    pda_quote_account,
+                    &accounts.token_program,
+                    ctx.remaining_accounts,
+                )?;
+            }
+        }
         
-        // ═══ PROGRAM VALIDATION ═══
-        require!(
-            ctx.accounts.target_program.key() == PUMP_FUN_PROGRAM,
-            MmWalletError::InvalidProgram
+        let holdings = BalanceSnapshot::capture_holdings(
+            &accounts.pda_wallet,
+            &accounts.pda_token_account,
+            accounts.pda_quote_account.as_deref(),
         );
+        settle_trade(&mut accounts.mm_wallet, accounts.risk_limits.as_mut(), &fill, holdings, clock.unix_timestamp)
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════════
+    // FEE CLAIMING
+    // ═══════════════════════════════════════════════════════════════════════════
+
+    /// Claim creator fees from Pump.fun
+    pub fn claim_fees(ctx: Context<ClaimFees>) -> Result<()> {
+        let wallet = &mut ctx.accounts.mm_wallet;
+        let clock = Clock::get()?;
+        
+        // Check caller holds the claim role
+        caller_permissions(
+            wallet,
+            &ctx.accounts.caller.key(),
+            ctx.accounts.operator_registry.as_deref(),
+            ctx.accounts.session.as_deref(),
+            &clock,
+        )?
+        .require(ROLE_CLAIM_FEES)?;
+        
+        // Check not paused (wallet and protocol)
+        require!(!wallet.paused, MmWalletError::TradingPaused);
+        ctx.accounts.protocol_config.check_cpi_allowed(&PUMP_FUN_PROGRAM)?;
+        
+        // Check is creator
+        require!(wallet.is_creator, MmWalletError::Unauthorized);
         
-        // ═══ SLIPPAGE PROTECTION (calculated on-chain) ═══
-        let min_sol_out = wallet.calculate_min_output(expected_sol)?;
+        // Record balance before claim
+        let balance_before = ctx.accounts.pda_wallet.lamports();
         
-        // ═══ BUILD CPI ═══
-        let owner_key = wallet.owner;
+        // Build signer seeds for PDA
+        let seed_owner = wallet.seed_owner;
         let nonce_bytes = wallet.nonce.to_le_bytes();
         let seeds = &[
             b"mm_wallet",
-            owner_key.as_ref(),
+            seed_owner.as_ref(),
             nonce_bytes.as_ref(),
             &[wallet.bump],
         ];
         let signer_seeds = &[&seeds[..]];
         
-        let mut data = PUMP_SELL_DISCRIMINATOR.to_vec();
-        data.extend_from_slice(&token_amount.to_le_bytes());
-        data.extend_from_slice(&min_sol_out.to_le_bytes());
+        let data = PUMP_WITHDRAW_DISCRIMINATOR.to_vec();
         
         let ix = anchor_lang::solana_program::instruction::Instruction {
             program_id: PUMP_FUN_PROGRAM,
@@ -770,189 +2076,219 @@ pub mod mm_wallet_v2 {
         
         invoke_signed(&ix, ctx.remaining_accounts, signer_seeds)?;
         
-        // ═══ UPDATE STATS ═══
-        wallet.total_volume = wallet.total_volume.saturating_add(expected_sol);
-        wallet.total_trades = wallet.total_trades.saturating_add(1);
-        wallet.last_trade = clock.unix_timestamp;
+        // Calculate fees claimed
+        let balance_after = ctx.accounts.pda_wallet.lamports();
+        let fees_claimed = balance_after.saturating_sub(balance_before);
+        
+        wallet.total_fees_claimed = wallet.total_fees_claimed.saturating_add(fees_claimed);
         
         // Emit event
-        emit!(TradeExecuted {
+        emit!(FeesClaimed {
             wallet: ctx.accounts.mm_wallet.key(),
-            trade_type: 1, // sell
-            amount_in: token_amount,
-            min_amount_out: min_sol_out,
+            venue: Venue::BondingCurve as u8,
+            amount: fees_claimed,
             timestamp: clock.unix_timestamp,
         });
         
-        msg!("Executed sell: {} tokens, min SOL: {}", token_amount, min_sol_out);
+        msg!("Claimed {} lamports in fees", fees_claimed);
         
         Ok(())
     }
 
-    /// Execute a swap on PumpSwap AMM (for migrated tokens)
-    pub fn execute_swap(
-        ctx: Context<ExecuteSwap>,
-        amount_in: u64,
-        expected_out: u64,
-        is_buy: bool,
-    ) -> Result<()> {
-        let wallet = &mut ctx.accounts.mm_wallet;
+    /// Claim coin-creator fees from the PumpSwap creator vault (migrated tokens)
+    /// 
+    /// Permissionless: the fees always land in the PDA, so any keeper may call this.
+    /// The fees arrive as WSOL; the PDA's WSOL account is closed into the PDA to unwrap
+    /// them, then recreated with the caller as payer and the caller refunded its rent.
+    pub fn claim_swap_fees<'info>(ctx: Context<'_, '_, '_, 'info, ClaimSwapFees<'info>>) -> Result<()> {
         let clock = Clock::get()?;
+        let wallet = &ctx.accounts.mm_wallet;
         
-        // ═══ AUTHORIZATION ═══
-        require!(
-            wallet.is_authorized(&ctx.accounts.caller.key()),
-            MmWalletError::UnauthorizedOperator
-        );
-        
-        // ═══ STATE CHECKS ═══
+        // Check not paused (wallet and protocol)
         require!(!wallet.paused, MmWalletError::TradingPaused);
+        ctx.accounts.protocol_config.check_cpi_allowed(&PUMPSWAP_PROGRAM)?;
         
-        // ═══ RATE LIMITING ═══
-        require!(
-            wallet.can_trade(clock.unix_timestamp),
-            MmWalletError::TradeTooSoon
-        );
+        // Check is creator
+        require!(wallet.is_creator, MmWalletError::Unauthorized);
         
-        // ═══ PROGRAM VALIDATION ═══
-        require!(
-            ctx.accounts.target_program.key() == PUMPSWAP_PROGRAM,
-            MmWalletError::InvalidProgram
-        );
+        pumpswap::validate_creator_fee_accounts(
+            ctx.remaining_accounts,
+            ctx.accounts.pda_wallet.key,
+            &ctx.accounts.pda_quote_account.key(),
+        )?;
         
-        // For buys, validate amount against balance
-        if is_buy {
-            let pda_balance = ctx.accounts.pda_wallet.lamports();
-            let available = pda_balance.saturating_sub(MIN_RENT_RESERVE);
-            
-            let max_trade = wallet.max_trade_amount(available)?;
-            require!(amount_in <= max_trade, MmWalletError::TradeExceedsMax);
-            require!(amount_in <= available, MmWalletError::InsufficientBalance);
-        }
+        // ═══ CLAIM ═══
+        let wrapped_before = ctx.accounts.pda_quote_account.amount;
+        let ix = venue_instruction(
+            PUMPSWAP_PROGRAM,
+            ctx.remaining_accounts,
+            pumpswap::COLLECT_COIN_CREATOR_FEE_DISCRIMINATOR.to_vec(),
+        );
+        wallet.invoke_signed(&ix, ctx.remaining_accounts)?;
         
-        // ═══ SLIPPAGE PROTECTION ═══
-        let min_amount_out = wallet.calculate_min_output(expected_out)?;
+        ctx.accounts.pda_quote_account.reload()?;
+        let fees_claimed = ctx.accounts.pda_quote_account.amount.saturating_sub(wrapped_before);
         
-        // ═══ BUILD CPI ═══
-        let owner_key = wallet.owner;
+        // ═══ UNWRAP ═══
+        let seed_owner = wallet.seed_owner;
         let nonce_bytes = wallet.nonce.to_le_bytes();
         let seeds = &[
             b"mm_wallet",
-            owner_key.as_ref(),
+            seed_owner.as_ref(),
             nonce_bytes.as_ref(),
             &[wallet.bump],
         ];
         let signer_seeds = &[&seeds[..]];
         
-        // PumpSwap uses different discriminators
-        let discriminator = if is_buy {
-            [248, 198, 158, 145, 225, 117, 135, 200]
-        } else {
-            [51, 230, 133, 164, 1, 127, 131, 173]
-        };
-        
-        let mut data = discriminator.to_vec();
-        data.extend_from_slice(&amount_in.to_le_bytes());
-        data.extend_from_slice(&min_amount_out.to_le_bytes());
-        
-        let ix = anchor_lang::solana_program::instruction::Instruction {
-            program_id: PUMPSWAP_PROGRAM,
-            accounts: ctx.remaining_accounts.iter().map(|a| {
-                if a.is_writable {
-                    anchor_lang::solana_program::instruction::AccountMeta::new(*a.key, a.is_signer)
-                } else {
-                    anchor_lang::solana_program::instruction::AccountMeta::new_readonly(*a.key, a.is_signer)
-                }
-            }).collect(),
-            data,
-        };
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.pda_quote_account.to_account_info(),
+                destination: ctx.accounts.pda_wallet.to_account_info(),
+                authority: ctx.accounts.pda_wallet.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+        
+        associated_token::create_idempotent(CpiContext::new(
+            ctx.accounts.associated_token_program.to_account_info(),
+            associated_token::Create {
+                payer: ctx.accounts.caller.to_account_info(),
+                associated_token: ctx.accounts.pda_quote_account.to_account_info(),
+                authority: ctx.accounts.pda_wallet.to_account_info(),
+                mint: ctx.accounts.quote_mint.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            },
+        ))?;
         
-        invoke_signed(&ix, ctx.remaining_accounts, signer_seeds)?;
+        // Refund the caller the rent it fronted (the PDA got it back from the close)
+        let rent = Rent::get()?.minimum_balance(TokenAccount::LEN);
+        **ctx.accounts.pda_wallet.try_borrow_mut_lamports()? -= rent;
+        **ctx.accounts.caller.to_account_info().try_borrow_mut_lamports()? += rent;
         
         // ═══ UPDATE STATS ═══
-        wallet.total_volume = wallet.total_volume.saturating_add(amount_in);
-        wallet.total_trades = wallet.total_trades.saturating_add(1);
-        wallet.last_trade = clock.unix_timestamp;
+        let wallet = &mut ctx.accounts.mm_wallet;
+        wallet.total_fees_claimed = wallet.total_fees_claimed.saturating_add(fees_claimed);
+        wallet.swap_fees_claimed = wallet.swap_fees_claimed.saturating_add(fees_claimed);
         
-        // Emit event
-        emit!(TradeExecuted {
-            wallet: ctx.accounts.mm_wallet.key(),
-            trade_type: if is_buy { 0 } else { 1 },
-            amount_in,
-            min_amount_out,
+        emit!(FeesClaimed {
+            wallet: wallet.key(),
+            venue: Venue::PumpSwap as u8,
+            amount: fees_claimed,
             timestamp: clock.unix_timestamp,
         });
         
-        msg!("Executed swap: {} (buy: {})", amount_in, is_buy);
+        msg!("Claimed {} lamports in PumpSwap creator fees", fees_claimed);
         
         Ok(())
     }
 
     // ═══════════════════════════════════════════════════════════════════════════
-    // FEE CLAIMING
+    // FEE SPLITTING
     // ═══════════════════════════════════════════════════════════════════════════
 
-    /// Claim creator fees from Pump.fun
-    pub fn claim_fees(ctx: Context<ClaimFees>) -> Result<()> {
-        let wallet = &mut ctx.accounts.mm_wallet;
-        let clock = Clock::get()?;
+    /// Create the wallet's fee split table (owner only)
+    /// 
+    /// Only fees claimed from now on are distributed; earlier claims stay with the wallet.
+    pub fn init_fee_split(ctx: Context<InitFeeSplit>, shares: Vec<FeeShare>) -> Result<()> {
+        // Check ownership (or an approved multisig proposal)
+        check_owner_action(
+            &ctx.accounts.mm_wallet,
+            &ctx.accounts.owner,
+            &ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            ProposalAction::UpdateFeeSplit { shares: shares.clone() },
+        )?;
         
-        // Check is authorized (owner or operator can claim)
-        require!(
-            wallet.is_authorized(&ctx.accounts.caller.key()),
-            MmWalletError::UnauthorizedOperator
-        );
+        let fee_split = &mut ctx.accounts.fee_split;
         
-        // Check not paused
-        require!(!wallet.paused, MmWalletError::TradingPaused);
+        fee_split.wallet = ctx.accounts.mm_wallet.key();
+        fee_split.bump = *ctx.bumps.get("fee_split").unwrap();
+        fee_split.recipients = Vec::new();
+        fee_split.reserved = [0u8; 32];
+        fee_split.init_table(&shares, ctx.accounts.mm_wallet.total_fees_claimed)?;
         
-        // Check is creator
-        require!(wallet.is_creator, MmWalletError::Unauthorized);
+        ctx.accounts.mm_wallet.fee_split = true;
         
-        // Record balance before claim
-        let balance_before = ctx.accounts.pda_wallet.lamports();
+        emit!(FeeSplitUpdated {
+            wallet: ctx.accounts.mm_wallet.key(),
+            shares,
+        });
         
-        // Build signer seeds for PDA
-        let owner_key = wallet.owner;
-        let nonce_bytes = wallet.nonce.to_le_bytes();
-        let seeds = &[
-            b"mm_wallet",
-            owner_key.as_ref(),
-            nonce_bytes.as_ref(),
-            &[wallet.bump],
-        ];
-        let signer_seeds = &[&seeds[..]];
+        msg!("Fee split initialized");
         
-        let data = PUMP_WITHDRAW_DISCRIMINATOR.to_vec();
+        Ok(())
+    }
+
+    /// Replace the fee split table (owner only)
+    /// 
+    /// Fees already claimed are owed to the current table, so a changed table is only
+    /// accepted once `distribute_fees` has paid them out.
+    pub fn update_fee_split(ctx: Context<UpdateFeeSplit>, shares: Vec<FeeShare>) -> Result<()> {
+        // Check ownership (or an approved multisig proposal)
+        check_owner_action(
+            &ctx.accounts.mm_wallet,
+            &ctx.accounts.owner,
+            &ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            ProposalAction::UpdateFeeSplit { shares: shares.clone() },
+        )?;
         
-        let ix = anchor_lang::solana_program::instruction::Instruction {
-            program_id: PUMP_FUN_PROGRAM,
-            accounts: ctx.remaining_accounts.iter().map(|a| {
-                if a.is_writable {
-                    anchor_lang::solana_program::instruction::AccountMeta::new(*a.key, a.is_signer)
-                } else {
-                    anchor_lang::solana_program::instruction::AccountMeta::new_readonly(*a.key, a.is_signer)
-                }
-            }).collect(),
-            data,
-        };
+        ctx.accounts.fee_split.set_table(&shares, ctx.accounts.mm_wallet.total_fees_claimed)?;
         
-        invoke_signed(&ix, ctx.remaining_accounts, signer_seeds)?;
+        emit!(FeeSplitUpdated {
+            wallet: ctx.accounts.mm_wallet.key(),
+            shares,
+        });
         
-        // Calculate fees claimed
-        let balance_after = ctx.accounts.pda_wallet.lamports();
-        let fees_claimed = balance_after.saturating_sub(balance_before);
+        msg!("Fee split updated");
         
-        wallet.total_fees_claimed = wallet.total_fees_claimed.saturating_add(fees_claimed);
+        Ok(())
+    }
+
+    /// Pay claimed-but-undistributed creator fees out according to the fee split
+    /// 
+    /// Permissionless: payouts only go to the recipients in the table, passed as
+    /// writable remaining accounts in table order. Pays at most what the PDA holds
+    /// above the rent reserve and the principal; the rest stays pending.
+    pub fn distribute_fees<'info>(ctx: Context<'_, '_, '_, 'info, DistributeFees<'info>>) -> Result<()> {
+        let clock = Clock::get()?;
+        let fee_split = &mut ctx.accounts.fee_split;
+        let pda_wallet = &ctx.accounts.pda_wallet;
         
-        // Emit event
-        emit!(FeesClaimed {
+        // Validate recipient accounts
+        require!(
+            ctx.remaining_accounts.len() == fee_split.recipients.len(),
+            MmWalletError::InvalidRemainingAccounts
+        );
+        for (i, recipient) in fee_split.recipients.iter().enumerate() {
+            require_remaining_key(ctx.remaining_accounts, i, &recipient.key, MmWalletError::InvalidFeeSplit)?;
+        }
+        
+        let undistributed = fee_split.undistributed(ctx.accounts.mm_wallet.total_fees_claimed);
+        let available = ctx.accounts.mm_wallet.withdrawable_profit(pda_wallet.lamports());
+        let amount = undistributed.min(available);
+        
+        let payouts = fee_split.shares(amount);
+        require!(payouts.iter().any(|p| *p > 0), MmWalletError::NothingToDistribute);
+        
+        for (account, payout) in ctx.remaining_accounts.iter().zip(&payouts) {
+            **pda_wallet.try_borrow_mut_lamports()? -= *payout;
+            **account.try_borrow_mut_lamports()? += *payout;
+        }
+        let paid = fee_split.record_payout(&payouts);
+        record_withdrawal(&ctx.accounts.mm_wallet, ctx.accounts.risk_limits.as_mut(), paid)?;
+        
+        emit!(FeesDistributed {
             wallet: ctx.accounts.mm_wallet.key(),
-            amount: fees_claimed,
+            amount: paid,
+            payouts,
+            undistributed: fee_split.undistributed(ctx.accounts.mm_wallet.total_fees_claimed),
             timestamp: clock.unix_timestamp,
         });
         
-        msg!("Claimed {} lamports in fees", fees_claimed);
+        msg!("Distributed {} lamports in fees", paid);
         
         Ok(())
     }
@@ -970,11 +2306,14 @@ pub mod mm_wallet_v2 {
     ) -> Result<()> {
         let wallet = &mut ctx.accounts.mm_wallet;
         
-        // Only owner can create token
-        require!(
-            ctx.accounts.owner.key() == wallet.owner,
-            MmWalletError::Unauthorized
-        );
+        // Check ownership (or an approved multisig proposal)
+        check_owner_action(
+            wallet,
+            &ctx.accounts.owner,
+            &ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            ProposalAction::CreateToken { metadata_hash: multisig::token_metadata_hash(&name, &symbol, &uri) },
+        )?;
         
         // Ensure token not already created
         require!(
@@ -983,11 +2322,11 @@ pub mod mm_wallet_v2 {
         );
         
         // Build signer seeds for PDA
-        let owner_key = wallet.owner;
+        let seed_owner = wallet.seed_owner;
         let nonce_bytes = wallet.nonce.to_le_bytes();
         let seeds = &[
             b"mm_wallet",
-            owner_key.as_ref(),
+            seed_owner.as_ref(),
             nonce_bytes.as_ref(),
             &[wallet.bump],
         ];
@@ -1029,11 +2368,14 @@ pub mod mm_wallet_v2 {
     pub fn set_token_mint(ctx: Context<SetTokenMint>) -> Result<()> {
         let wallet = &mut ctx.accounts.mm_wallet;
         
-        // Check ownership
-        require!(
-            ctx.accounts.owner.key() == wallet.owner,
-            MmWalletError::Unauthorized
-        );
+        // Check ownership (or an approved multisig proposal)
+        check_owner_action(
+            wallet,
+            &ctx.accounts.owner,
+            &ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            ProposalAction::SetTokenMint { mint: ctx.accounts.token_mint_account.key() },
+        )?;
         
         // Ensure not already set
         require!(
@@ -1057,20 +2399,30 @@ pub mod mm_wallet_v2 {
 
     /// Update trading strategy
     pub fn update_strategy(
-        ctx: Context<UpdateConfig>,
+        ctx: Context<UpdateStrategy>,
         strategy: Strategy,
         config: StrategyConfig,
     ) -> Result<()> {
         let wallet = &mut ctx.accounts.mm_wallet;
         
-        // Check ownership
+        // Check ownership (or an approved multisig proposal)
+        check_owner_action(
+            wallet,
+            &ctx.accounts.owner,
+            &ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            ProposalAction::UpdateStrategy { strategy, config },
+        )?;
+        
+        // Locked wallets go through queue_config_change
         require!(
-            ctx.accounts.owner.key() == wallet.owner,
-            MmWalletError::Unauthorized
+            !wallet.is_locked(Clock::get()?.unix_timestamp),
+            MmWalletError::ConfigChangeTimelocked
         );
         
         // Validate config
-        validate_config(&config)?;
+        let bounds = ctx.accounts.protocol_config.as_ref().map_or(ConfigBounds::DEFAULT, |p| p.bounds);
+        validate_config(&config, &bounds)?;
         
         let old_strategy = wallet.strategy as u8;
         wallet.strategy = strategy;
@@ -1089,13 +2441,22 @@ pub mod mm_wallet_v2 {
     }
 
     /// Update authorized operator
-    pub fn set_operator(ctx: Context<UpdateConfig>, new_operator: Pubkey) -> Result<()> {
+    pub fn set_operator(ctx: Context<OwnerAction>, new_operator: Pubkey) -> Result<()> {
         let wallet = &mut ctx.accounts.mm_wallet;
         
-        // Check ownership
+        // Check ownership (or an approved multisig proposal)
+        check_owner_action(
+            wallet,
+            &ctx.accounts.owner,
+            &ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            ProposalAction::SetOperator { operator: new_operator },
+        )?;
+        
+        // Locked wallets go through queue_config_change
         require!(
-            ctx.accounts.owner.key() == wallet.owner,
-            MmWalletError::Unauthorized
+            !wallet.is_locked(Clock::get()?.unix_timestamp),
+            MmWalletError::ConfigChangeTimelocked
         );
         
         // Validate operator is not zero
@@ -1119,15 +2480,14 @@ pub mod mm_wallet_v2 {
         Ok(())
     }
 
-    /// Pause trading
-    pub fn pause(ctx: Context<UpdateConfig>) -> Result<()> {
+    /// Pause trading (owner or operator with the pause role)
+    pub fn pause(ctx: Context<Pause>) -> Result<()> {
         let wallet = &mut ctx.accounts.mm_wallet;
         
-        // Check ownership
-        require!(
-            ctx.accounts.owner.key() == wallet.owner,
-            MmWalletError::Unauthorized
-        );
+        // Check caller holds the pause role
+        wallet
+            .permissions(&ctx.accounts.caller.key(), ctx.accounts.operator_registry.as_deref())?
+            .require(ROLE_PAUSE)?;
         
         wallet.paused = true;
         
@@ -1136,16 +2496,73 @@ pub mod mm_wallet_v2 {
         Ok(())
     }
 
-    /// Resume trading
-    pub fn resume(ctx: Context<UpdateConfig>) -> Result<()> {
+    /// Set or clear (with `Pubkey::default()`) the wallet's guardian
+    pub fn set_guardian(ctx: Context<OwnerAction>, new_guardian: Pubkey) -> Result<()> {
         let wallet = &mut ctx.accounts.mm_wallet;
         
-        // Check ownership
+        // Check ownership (or an approved multisig proposal)
+        check_owner_action(
+            wallet,
+            &ctx.accounts.owner,
+            &ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            ProposalAction::SetGuardian { guardian: new_guardian },
+        )?;
+        
+        let old_guardian = wallet.guardian;
+        wallet.guardian = new_guardian;
+        
+        // Emit event
+        emit!(GuardianChanged {
+            wallet: ctx.accounts.mm_wallet.key(),
+            old_guardian,
+            new_guardian,
+        });
+        
+        msg!("Guardian changed from {} to {}", old_guardian, new_guardian);
+        
+        Ok(())
+    }
+
+    /// Pause trading as the wallet's guardian (pausing is all a guardian can do)
+    pub fn guardian_pause(ctx: Context<GuardianPause>, reason: u8) -> Result<()> {
+        let wallet = &mut ctx.accounts.mm_wallet;
+        let clock = Clock::get()?;
+        
+        // Check guardian
         require!(
-            ctx.accounts.owner.key() == wallet.owner,
+            wallet.is_guardian(&ctx.accounts.guardian.key()),
             MmWalletError::Unauthorized
         );
         
+        wallet.paused = true;
+        
+        // Emit event
+        emit!(GuardianPaused {
+            wallet: ctx.accounts.mm_wallet.key(),
+            guardian: ctx.accounts.guardian.key(),
+            reason,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        msg!("Trading paused by guardian (reason: {})", reason);
+        
+        Ok(())
+    }
+
+    /// Resume trading
+    pub fn resume(ctx: Context<OwnerAction>) -> Result<()> {
+        let wallet = &mut ctx.accounts.mm_wallet;
+        
+        // Check ownership (or an approved multisig proposal)
+        check_owner_action(
+            wallet,
+            &ctx.accounts.owner,
+            &ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            ProposalAction::Resume,
+        )?;
+        
         wallet.paused = false;
         
         msg!("Trading resumed");
@@ -1154,15 +2571,21 @@ pub mod mm_wallet_v2 {
     }
 
     /// Extend lock period (can only increase, never decrease)
-    pub fn extend_lock(ctx: Context<UpdateConfig>, additional_seconds: i64) -> Result<()> {
+    /// 
+    /// Pushes back the end of an unfinished vesting schedule; otherwise, or while the
+    /// `lock_until` cliff is still running, extends the cliff.
+    pub fn extend_lock(ctx: Context<OwnerAction>, additional_seconds: i64) -> Result<()> {
         let wallet = &mut ctx.accounts.mm_wallet;
         let clock = Clock::get()?;
         
-        // Check ownership
-        require!(
-            ctx.accounts.owner.key() == wallet.owner,
-            MmWalletError::Unauthorized
-        );
+        // Check ownership (or an approved multisig proposal)
+        check_owner_action(
+            wallet,
+            &ctx.accounts.owner,
+            &ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            ProposalAction::ExtendLock { additional_seconds },
+        )?;
         
         // Validate additional time
         require!(
@@ -1170,6 +2593,26 @@ pub mod mm_wallet_v2 {
             MmWalletError::InvalidLockDuration
         );
         
+        // Validate total lock doesn't exceed maximum (5 years from now)
+        let max_allowed = clock.unix_timestamp
+            .checked_add(MAX_TOTAL_LOCK_SECONDS)
+            .ok_or(MmWalletError::MathOverflow)?;
+        
+        // Extend an unfinished vesting schedule
+        let vesting_active = wallet.vesting.is_set() && wallet.vesting.end > clock.unix_timestamp;
+        if vesting_active {
+            wallet.vesting.extend(additional_seconds)?;
+            require!(wallet.vesting.end <= max_allowed, MmWalletError::LockTooLong);
+            
+            emit_vesting_updated(wallet.key(), &wallet.vesting);
+            
+            msg!("Vesting extended to: {}", wallet.vesting.end);
+            
+            if !wallet.is_locked(clock.unix_timestamp) {
+                return Ok(());
+            }
+        }
+        
         // Calculate new lock time
         let new_lock = if wallet.lock_until > clock.unix_timestamp {
             wallet.lock_until
@@ -1181,11 +2624,6 @@ pub mod mm_wallet_v2 {
                 .ok_or(MmWalletError::MathOverflow)?
         };
         
-        // Validate total lock doesn't exceed maximum (5 years from now)
-        let max_allowed = clock.unix_timestamp
-            .checked_add(MAX_TOTAL_LOCK_SECONDS)
-            .ok_or(MmWalletError::MathOverflow)?;
-        
         require!(new_lock <= max_allowed, MmWalletError::LockTooLong);
         
         wallet.lock_until = new_lock;
@@ -1194,294 +2632,3852 @@ pub mod mm_wallet_v2 {
         
         Ok(())
     }
-}
 
-// ═══════════════════════════════════════════════════════════════════════════════
-// VALIDATION HELPERS
-// ═══════════════════════════════════════════════════════════════════════════════
+    // ═══════════════════════════════════════════════════════════════════════════
+    // TIMELOCKED CONFIG CHANGES (Owner only, while locked)
+    // ═══════════════════════════════════════════════════════════════════════════
 
-fn validate_config(config: &StrategyConfig) -> Result<()> {
-    // Trade size: 1-50%
-    require!(
-        config.trade_size_pct >= 1 && config.trade_size_pct <= MAX_TRADE_PCT,
-        MmWalletError::InvalidTradeSize
-    );
-    
-    // Slippage: 0.1% - 50% (10-5000 bps)
-    require!(
-        config.slippage_bps >= MIN_SLIPPAGE_BPS && config.slippage_bps <= MAX_SLIPPAGE_BPS,
-        MmWalletError::InvalidSlippage
-    );
-    
-    // Delays: min <= max
-    require!(
-        config.min_delay_secs <= config.max_delay_secs,
-        MmWalletError::InvalidDelayConfig
-    );
-    
-    Ok(())
-}
+    /// Queue a strategy, operator or session change, applicable after `CONFIG_CHANGE_DELAY_SECONDS`
+    /// 
+    /// Locked wallets reject `update_strategy`, `set_operator`, `add_operator`,
+    /// `update_operator` and `create_session`; this is their only path. One change can be
+    /// pending at a time.
+    pub fn queue_config_change(ctx: Context<QueueConfigChange>, change: ConfigChange) -> Result<()> {
+        // Check ownership (or an approved multisig proposal)
+        check_owner_action(
+            &ctx.accounts.mm_wallet,
+            &ctx.accounts.owner,
+            &ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            change.proposal_action(),
+        )?;
+        
+        let clock = Clock::get()?;
+        let bounds = ctx.accounts.protocol_config.as_ref().map_or(ConfigBounds::DEFAULT, |p| p.bounds);
+        change.validate(&bounds, &clock)?;
+        
+        let now = clock.unix_timestamp;
+        let eta = now
+            .checked_add(CONFIG_CHANGE_DELAY_SECONDS)
+            .ok_or(MmWalletError::MathOverflow)?;
+        
+        let pending = &mut ctx.accounts.pending_change;
+        
+        pending.wallet = ctx.accounts.mm_wallet.key();
+        pending.bump = *ctx.bumps.get("pending_change").unwrap();
+        pending.change = change;
+        pending.queued_at = now;
+        pending.eta = eta;
+        pending.reserved = [0u8; 32];
+        
+        // Emit event
+        emit!(ConfigChangeQueued {
+            wallet: ctx.accounts.mm_wallet.key(),
+            kind: change.kind(),
+            change,
+            queued_at: now,
+            eta,
+        });
+        
+        msg!("Config change queued (kind: {}, eta: {})", change.kind(), eta);
+        
+        Ok(())
+    }
 
-// ═══════════════════════════════════════════════════════════════════════════════
-// ACCOUNT CONTEXTS
-// ═══════════════════════════════════════════════════════════════════════════════
+    /// Apply a queued change once its delay has elapsed
+    pub fn apply_config_change(ctx: Context<ApplyConfigChange>) -> Result<()> {
+        let wallet = &mut ctx.accounts.mm_wallet;
+        
+        // Check ownership (or an approved multisig proposal)
+        check_owner_action(
+            wallet,
+            &ctx.accounts.owner,
+            &ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            ProposalAction::ApplyConfigChange { queued_at: ctx.accounts.pending_change.queued_at },
+        )?;
+        
+        let clock = Clock::get()?;
+        let pending = &ctx.accounts.pending_change;
+        require!(
+            pending.is_ready(clock.unix_timestamp),
+            MmWalletError::ConfigChangeNotReady
+        );
+        
+        // Protocol bounds (and session expiries) may have moved since the change was queued
+        let change = pending.change;
+        let bounds = ctx.accounts.protocol_config.as_ref().map_or(ConfigBounds::DEFAULT, |p| p.bounds);
+        change.validate(&bounds, &clock)?;
+        change.apply(wallet, ctx.accounts.operator_registry.as_deref_mut())?;
+        
+        if let ConfigChange::CreateSession { session_key, roles, expires_at, expires_at_slot, allowance_lamports } = change {
+            let session = ctx.accounts.session.as_mut().ok_or(error!(MmWalletError::SessionAccountRequired))?;
+            
+            session.wallet = wallet.key();
+            session.key = session_key;
+            session.bump = *ctx.bumps.get("session").unwrap();
+            session.roles = roles;
+            session.expires_at = expires_at;
+            session.expires_at_slot = expires_at_slot;
+            session.allowance_lamports = allowance_lamports;
+            session.spent_lamports = 0;
+            session.revoked = false;
+            session.created_at = clock.unix_timestamp;
+            session.reserved = [0u8; 32];
+            
+            emit!(SessionCreated {
+                wallet: wallet.key(),
+                session_key,
+                roles,
+                expires_at,
+                expires_at_slot,
+                allowance_lamports,
+            });
+        }
+        
+        // Emit event
+        emit!(ConfigChangeApplied {
+            wallet: wallet.key(),
+            kind: change.kind(),
+        });
+        
+        msg!("Config change applied (kind: {})", change.kind());
+        
+        Ok(())
+    }
 
-#[derive(Accounts)]
-#[instruction(nonce: u64)]
-pub struct Initialize<'info> {
-    #[account(
-        init,
-        payer = owner,
-        space = MmWallet::SIZE,
-        seeds = [b"mm_wallet", owner.key().as_ref(), &nonce.to_le_bytes()],
-        bump
-    )]
-    pub mm_wallet: Account<'info, MmWallet>,
-    
-    /// CHECK: PDA for holding SOL
-    #[account(
-        seeds = [b"mm_wallet", owner.key().as_ref(), &nonce.to_le_bytes()],
-        bump
-    )]
-    pub pda_wallet: AccountInfo<'info>,
-    
-    #[account(mut)]
-    pub owner: Signer<'info>,
-    
-    pub system_program: Program<'info, System>,
-}
+    /// Drop a queued change and reclaim its rent
+    pub fn cancel_config_change(ctx: Context<CancelConfigChange>) -> Result<()> {
+        // Check ownership
+        require!(
+            ctx.accounts.owner.key() == ctx.accounts.mm_wallet.owner,
+            MmWalletError::Unauthorized
+        );
+        
+        let kind = ctx.accounts.pending_change.change.kind();
+        
+        // Emit event
+        emit!(ConfigChangeCancelled {
+            wallet: ctx.accounts.mm_wallet.key(),
+            kind,
+        });
+        
+        msg!("Config change cancelled (kind: {})", kind);
+        
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct Deposit<'info> {
-    pub mm_wallet: Account<'info, MmWallet>,
-    
-    /// CHECK: PDA for holding SOL
-    #[account(
-        mut,
-        seeds = [b"mm_wallet", mm_wallet.owner.as_ref(), &mm_wallet.nonce.to_le_bytes()],
-        bump = mm_wallet.bump
-    )]
-    pub pda_wallet: AccountInfo<'info>,
-    
-    #[account(mut)]
-    pub depositor: Signer<'info>,
-    
-    pub system_program: Program<'info, System>,
-}
+    // ═══════════════════════════════════════════════════════════════════════════
+    // PROTOCOL ADMIN
+    // ═══════════════════════════════════════════════════════════════════════════
 
-#[derive(Accounts)]
-pub struct Withdraw<'info> {
-    pub mm_wallet: Account<'info, MmWallet>,
-    
-    /// CHECK: PDA for holding SOL
-    #[account(
-        mut,
-        seeds = [b"mm_wallet", mm_wallet.owner.as_ref(), &mm_wallet.nonce.to_le_bytes()],
-        bump = mm_wallet.bump
-    )]
-    pub pda_wallet: AccountInfo<'info>,
-    
-    #[account(mut)]
-    pub owner: Signer<'info>,
-    
-    /// CHECK: Must be same as owner (validated in handler)
-    #[account(mut)]
-    pub destination: AccountInfo<'info>,
-    
-    pub system_program: Program<'info, System>,
-}
+    /// Create the protocol config singleton (program upgrade authority only)
+    /// 
+    /// Run right after deploying: trade and claim instructions require this account.
+    pub fn init_protocol_config(
+        ctx: Context<InitProtocolConfig>,
+        admin: Pubkey,
+        params: ProtocolConfigParams,
+    ) -> Result<()> {
+        require!(admin != Pubkey::default(), MmWalletError::Unauthorized);
+        params.validate()?;
+        
+        let config = &mut ctx.accounts.protocol_config;
+        
+        config.admin = admin;
+        config.bump = *ctx.bumps.get("protocol_config").unwrap();
+        config.paused = false;
+        config.reserved = [0u8; 24];
+        config.apply(params);
+        
+        emit_protocol_config_updated(config);
+        
+        msg!("Protocol config initialized, admin: {}", admin);
+        
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct WithdrawTokens<'info> {
-    pub mm_wallet: Account<'info, MmWallet>,
-    
-    /// CHECK: PDA for signing
-    #[account(
-        seeds = [b"mm_wallet", mm_wallet.owner.as_ref(), &mm_wallet.nonce.to_le_bytes()],
-        bump = mm_wallet.bump
-    )]
-    pub pda_wallet: AccountInfo<'info>,
-    
-    #[account(mut)]
-    pub pda_token_account: Account<'info, TokenAccount>,
-    
-    #[account(mut)]
-    pub owner_token_account: Account<'info, TokenAccount>,
-    
-    pub token_mint: Account<'info, Mint>,
-    
-    #[account(mut)]
-    pub owner: Signer<'info>,
-    
-    pub token_program: Program<'info, Token>,
-}
+    /// Update the guardian, program allowlist and config bounds
+    pub fn update_protocol_config(ctx: Context<UpdateProtocolConfig>, params: ProtocolConfigParams) -> Result<()> {
+        let config = &mut ctx.accounts.protocol_config;
+        
+        // Check admin
+        require!(ctx.accounts.caller.key() == config.admin, MmWalletError::Unauthorized);
+        
+        params.validate()?;
+        config.apply(params);
+        
+        emit_protocol_config_updated(config);
+        
+        msg!("Protocol config updated");
+        
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct ExecuteTrade<'info> {
-    #[account(mut)]
-    pub mm_wallet: Account<'info, MmWallet>,
-    
-    /// CHECK: PDA for signing
-    #[account(
-        mut,
-        seeds = [b"mm_wallet", mm_wallet.owner.as_ref(), &mm_wallet.nonce.to_le_bytes()],
-        bump = mm_wallet.bump
-    )]
-    pub pda_wallet: AccountInfo<'info>,
-    
-    /// CHECK: Must be Pump.fun program
-    pub target_program: AccountInfo<'info>,
-    
-    /// Caller must be owner or authorized operator
-    pub caller: Signer<'info>,
-    
-    // Remaining accounts are passed to Pump.fun CPI
-}
+    /// Hand the protocol admin role to a new key
+    pub fn set_protocol_admin(ctx: Context<UpdateProtocolConfig>, new_admin: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.protocol_config;
+        
+        // Check admin
+        require!(ctx.accounts.caller.key() == config.admin, MmWalletError::Unauthorized);
+        require!(new_admin != Pubkey::default(), MmWalletError::Unauthorized);
+        
+        let old_admin = config.admin;
+        config.admin = new_admin;
+        
+        emit!(ProtocolAdminChanged { old_admin, new_admin });
+        
+        msg!("Protocol admin changed from {} to {}", old_admin, new_admin);
+        
+        Ok(())
+    }
+
+    /// Halt trades and claims on every wallet (admin or protocol guardian)
+    pub fn protocol_pause(ctx: Context<UpdateProtocolConfig>, reason: u8) -> Result<()> {
+        let config = &mut ctx.accounts.protocol_config;
+        let caller = ctx.accounts.caller.key();
+        
+        require!(
+            caller == config.admin || config.is_guardian(&caller),
+            MmWalletError::Unauthorized
+        );
+        
+        config.paused = true;
+        
+        emit!(ProtocolPauseChanged {
+            by: caller,
+            paused: true,
+            reason,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        msg!("Protocol paused by {} (reason: {})", caller, reason);
+        
+        Ok(())
+    }
+
+    /// Lift the global pause (admin only)
+    pub fn protocol_resume(ctx: Context<UpdateProtocolConfig>) -> Result<()> {
+        let config = &mut ctx.accounts.protocol_config;
+        
+        // Check admin
+        require!(ctx.accounts.caller.key() == config.admin, MmWalletError::Unauthorized);
+        
+        config.paused = false;
+        
+        emit!(ProtocolPauseChanged {
+            by: ctx.accounts.caller.key(),
+            paused: false,
+            reason: guardian_reason::UNSPECIFIED,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        msg!("Protocol resumed");
+        
+        Ok(())
+    }
+
+    /// Move a wallet to another protocol fee tier (admin only)
+    pub fn set_fee_tier(ctx: Context<SetFeeTier>, tier: u8) -> Result<()> {
+        // Check admin
+        require!(
+            ctx.accounts.admin.key() == ctx.accounts.protocol_config.admin,
+            MmWalletError::Unauthorized
+        );
+        require!((tier as usize) < protocol::FEE_TIERS, MmWalletError::InvalidFeeTier);
+        
+        let wallet = &mut ctx.accounts.mm_wallet;
+        let old_tier = wallet.fee_tier;
+        wallet.fee_tier = tier;
+        
+        emit!(FeeTierChanged {
+            wallet: wallet.key(),
+            old_tier,
+            new_tier: tier,
+        });
+        
+        msg!("Fee tier changed from {} to {}", old_tier, tier);
+        
+        Ok(())
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════════
+    // OWNERSHIP
+    // ═══════════════════════════════════════════════════════════════════════════
+
+    /// Propose a new owner (step 1 of 2)
+    /// 
+    /// The PDA stays at its address because seeds use `seed_owner`, not `owner`.
+    /// Proposing `Pubkey::default()` cancels a pending transfer.
+    pub fn propose_owner(ctx: Context<OwnerAction>, new_owner: Pubkey) -> Result<()> {
+        let wallet = &mut ctx.accounts.mm_wallet;
+        
+        // Check ownership (or an approved multisig proposal)
+        check_owner_action(
+            wallet,
+            &ctx.accounts.owner,
+            &ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            ProposalAction::TransferOwnership { new_owner },
+        )?;
+        
+        wallet.pending_owner = new_owner;
+        
+        // Emit event
+        emit!(OwnershipTransferProposed {
+            wallet: ctx.accounts.mm_wallet.key(),
+            owner: ctx.accounts.owner.key(),
+            pending_owner: new_owner,
+        });
+        
+        msg!("Ownership transfer proposed to: {}", new_owner);
+        
+        Ok(())
+    }
+
+    /// Accept a proposed ownership transfer (step 2 of 2, signed by the new owner)
+    /// 
+    /// On multisig wallets the nominee was already approved by proposal at `propose_owner`.
+    pub fn accept_owner(ctx: Context<AcceptOwner>) -> Result<()> {
+        let wallet = &mut ctx.accounts.mm_wallet;
+        let new_owner = ctx.accounts.new_owner.key();
+        
+        require!(
+            wallet.pending_owner != Pubkey::default() && wallet.pending_owner == new_owner,
+            MmWalletError::NoPendingOwner
+        );
+        
+        let old_owner = wallet.owner;
+        wallet.owner = new_owner;
+        wallet.pending_owner = Pubkey::default();
+        
+        // Emit event
+        emit!(OwnershipTransferred {
+            wallet: ctx.accounts.mm_wallet.key(),
+            old_owner,
+            new_owner,
+        });
+        
+        msg!("Ownership transferred from {} to {}", old_owner, new_owner);
+        
+        Ok(())
+    }
+
+    /// Upgrade a v2 wallet to the current layout (adds `seed_owner`/`pending_owner`)
+    /// 
+    /// Keeps the PDA address. Anyone can pay for the extra rent.
+    pub fn migrate_wallet(ctx: Context<MigrateWallet>) -> Result<()> {
+        let info = ctx.accounts.mm_wallet.to_account_info();
+        
+        require!(info.owner == &crate::ID, MmWalletError::InvalidWalletAccount);
+        let old_size = info.data_len();
+        require!(old_size == MmWallet::V2_SIZE, MmWalletError::WalletAlreadyMigrated);
+        
+        // Top up rent for the larger account so trading balance isn't used
+        let rent = Rent::get()?;
+        let top_up = rent
+            .minimum_balance(MmWallet::SIZE)
+            .saturating_sub(rent.minimum_balance(old_size));
+        
+        if top_up > 0 {
+            let ix = system_instruction::transfer(&ctx.accounts.payer.key(), &info.key(), top_up);
+            anchor_lang::solana_program::program::invoke(
+                &ix,
+                &[
+                    ctx.accounts.payer.to_account_info(),
+                    info.clone(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )?;
+        }
+        
+        info.realloc(MmWallet::SIZE, true)?;
+        let balance = info.lamports();
+        MmWallet::migrate_data(&mut info.try_borrow_mut_data()?, balance)?;
+        
+        emit!(WalletMigrated {
+            wallet: info.key(),
+            version: PROGRAM_VERSION,
+        });
+        
+        msg!("Wallet migrated to v{}", PROGRAM_VERSION);
+        
+        Ok(())
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════════
+    // MULTISIG OWNERSHIP
+    // ═══════════════════════════════════════════════════════════════════════════
+
+    /// Put the wallet under M-of-N ownership
+    /// 
+    /// From then on withdrawals, ownership transfers, operator, session, strategy, lock,
+    /// token lock, fee split, recovery, guardian and risk limit changes, token creation,
+    /// `set_token_mint`, `resume` and queueing or applying config changes need an
+    /// approved proposal (see `ProposalAction`). Pausing, vetoes, cancellations,
+    /// `remove_operator` and `revoke_session` stay single-key (the `owner` key), so a
+    /// compromised operator or session can be cut off without waiting for approvals.
+    /// Withdrawals still pay `owner`.
+    pub fn init_multisig(ctx: Context<InitMultisig>, owners: Vec<Pubkey>, threshold: u8) -> Result<()> {
+        let wallet = &mut ctx.accounts.mm_wallet;
+        
+        // Check ownership
+        require!(
+            ctx.accounts.owner.key() == wallet.owner,
+            MmWalletError::Unauthorized
+        );
+        
+        let multisig = &mut ctx.accounts.multisig;
+        
+        multisig.wallet = wallet.key();
+        multisig.bump = *ctx.bumps.get("multisig").unwrap();
+        multisig.generation = 0;
+        multisig.proposal_count = 0;
+        multisig.reserved = [0u8; 32];
+        multisig.set_owners(owners, threshold)?;
+        
+        wallet.multisig = true;
+        
+        emit_multisig_updated(multisig);
+        
+        msg!("Multisig enabled: {} of {}", threshold, multisig.owners.len());
+        
+        Ok(())
+    }
+
+    /// Replace the multisig owner set through an approved `UpdateMultisig` proposal
+    /// 
+    /// Proposals created under the old owner set can no longer be approved or executed.
+    pub fn update_multisig(ctx: Context<UpdateMultisig>, owners: Vec<Pubkey>, threshold: u8) -> Result<()> {
+        let action = ProposalAction::UpdateMultisig { owners: owners.clone(), threshold };
+        
+        // Check the proposal
+        ctx.accounts.mm_wallet.authorize_owner_action(
+            &ctx.accounts.owner.key(),
+            Some(&ctx.accounts.multisig),
+            Some(&mut ctx.accounts.proposal),
+            &action,
+        )?;
+        
+        emit!(ProposalExecuted {
+            wallet: ctx.accounts.mm_wallet.key(),
+            proposal: ctx.accounts.proposal.key(),
+            executor: ctx.accounts.owner.key(),
+            kind: action.kind(),
+        });
+        
+        let multisig = &mut ctx.accounts.multisig;
+        multisig.set_owners(owners, threshold)?;
+        
+        emit_multisig_updated(multisig);
+        
+        msg!("Multisig updated: {} of {}", threshold, multisig.owners.len());
+        
+        Ok(())
+    }
+
+    /// Propose an owner action (multisig owners only; counts as the proposer's approval)
+    pub fn create_proposal(ctx: Context<CreateProposal>, action: ProposalAction) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let proposer = ctx.accounts.proposer.key();
+        
+        // Check the proposer is a multisig owner
+        multisig.owner_index(&proposer)?;
+        
+        if let ProposalAction::UpdateMultisig { owners, threshold } = &action {
+            OwnerMultisig::validate_owners(owners, *threshold)?;
+        }
+        
+        let proposal = &mut ctx.accounts.proposal;
+        let kind = action.kind();
+        
+        proposal.wallet = ctx.accounts.mm_wallet.key();
+        proposal.index = multisig.proposal_count;
+        proposal.bump = *ctx.bumps.get("proposal").unwrap();
+        proposal.proposer = proposer;
+        proposal.action = action;
+        proposal.generation = multisig.generation;
+        proposal.approvals = 0;
+        proposal.executed = false;
+        proposal.created_at = Clock::get()?.unix_timestamp;
+        proposal.reserved = [0u8; 16];
+        proposal.approve(multisig, &proposer)?;
+        
+        multisig.proposal_count = multisig
+            .proposal_count
+            .checked_add(1)
+            .ok_or(MmWalletError::MathOverflow)?;
+        
+        // Emit event
+        emit!(ProposalCreated {
+            wallet: proposal.wallet,
+            proposal: proposal.key(),
+            index: proposal.index,
+            proposer,
+            kind,
+        });
+        
+        msg!("Proposal {} created (kind: {})", proposal.index, kind);
+        
+        Ok(())
+    }
+
+    /// Approve a pending proposal (multisig owners only)
+    pub fn approve_proposal(ctx: Context<ApproveProposal>) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+        let proposal = &mut ctx.accounts.proposal;
+        
+        let approvals = proposal.approve(&ctx.accounts.multisig, &owner)?;
+        
+        // Emit event
+        emit!(ProposalApproved {
+            wallet: proposal.wallet,
+            proposal: proposal.key(),
+            owner,
+            approvals: approvals as u8,
+        });
+        
+        msg!("Proposal {} approved by {} ({} approvals)", proposal.index, owner, approvals);
+        
+        Ok(())
+    }
+
+    /// Close a proposal and return its rent (proposer only; cancels it if not yet executed)
+    pub fn close_proposal(ctx: Context<CloseProposal>) -> Result<()> {
+        let proposal = &ctx.accounts.proposal;
+        
+        require!(
+            ctx.accounts.proposer.key() == proposal.proposer,
+            MmWalletError::Unauthorized
+        );
+        
+        // Emit event
+        emit!(ProposalClosed {
+            wallet: proposal.wallet,
+            proposal: proposal.key(),
+            executed: proposal.executed,
+        });
+        
+        msg!("Proposal {} closed", proposal.index);
+        
+        Ok(())
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════════
+    // SOCIAL RECOVERY
+    // ═══════════════════════════════════════════════════════════════════════════
+
+    /// Register recovery guardians that can rotate a lost owner key
+    /// 
+    /// Once `threshold` guardians back a recovery, the owner has `challenge_seconds`
+    /// to veto it before anyone can execute it. Works while the wallet is locked.
+    pub fn init_recovery(
+        ctx: Context<InitRecovery>,
+        guardians: Vec<Pubkey>,
+        threshold: u8,
+        challenge_seconds: i64,
+    ) -> Result<()> {
+        // Check ownership (or an approved multisig proposal)
+        check_owner_action(
+            &ctx.accounts.mm_wallet,
+            &ctx.accounts.owner,
+            &ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            ProposalAction::UpdateRecovery {
+                guardians: guardians.clone(),
+                threshold,
+                challenge_seconds,
+            },
+        )?;
+        
+        let recovery = &mut ctx.accounts.recovery;
+        
+        recovery.wallet = ctx.accounts.mm_wallet.key();
+        recovery.bump = *ctx.bumps.get("recovery").unwrap();
+        recovery.reserved = [0u8; 32];
+        recovery.set_guardians(guardians, threshold, challenge_seconds)?;
+        
+        emit_recovery_config_updated(recovery);
+        
+        msg!("Recovery enabled: {} of {} guardians", threshold, recovery.guardians.len());
+        
+        Ok(())
+    }
+
+    /// Replace recovery guardians, threshold and challenge period (drops a recovery in progress)
+    pub fn update_recovery(
+        ctx: Context<UpdateRecovery>,
+        guardians: Vec<Pubkey>,
+        threshold: u8,
+        challenge_seconds: i64,
+    ) -> Result<()> {
+        // Check ownership (or an approved multisig proposal)
+        check_owner_action(
+            &ctx.accounts.mm_wallet,
+            &ctx.accounts.owner,
+            &ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            ProposalAction::UpdateRecovery {
+                guardians: guardians.clone(),
+                threshold,
+                challenge_seconds,
+            },
+        )?;
+        
+        let recovery = &mut ctx.accounts.recovery;
+        
+        if recovery.is_active() {
+            emit!(RecoveryVetoed {
+                wallet: recovery.wallet,
+                owner: ctx.accounts.owner.key(),
+                new_owner: recovery.new_owner,
+            });
+        }
+        
+        recovery.set_guardians(guardians, threshold, challenge_seconds)?;
+        
+        emit_recovery_config_updated(recovery);
+        
+        msg!("Recovery updated: {} of {} guardians", threshold, recovery.guardians.len());
+        
+        Ok(())
+    }
+
+    /// Start a recovery towards `new_owner` (recovery guardians only; counts as their support)
+    pub fn start_recovery(ctx: Context<GuardianRecovery>, new_owner: Pubkey) -> Result<()> {
+        let guardian = ctx.accounts.guardian.key();
+        let recovery = &mut ctx.accounts.recovery;
+        
+        let approvals = recovery.start(&guardian, new_owner, Clock::get()?.unix_timestamp)?;
+        
+        // Emit event
+        emit!(RecoveryStarted {
+            wallet: recovery.wallet,
+            guardian,
+            new_owner,
+            approvals: approvals as u8,
+            executable_at: recovery.executable_at,
+        });
+        
+        msg!("Recovery started by {} towards {}", guardian, new_owner);
+        
+        Ok(())
+    }
+
+    /// Back the recovery in progress (recovery guardians only)
+    pub fn support_recovery(ctx: Context<GuardianRecovery>) -> Result<()> {
+        let guardian = ctx.accounts.guardian.key();
+        let recovery = &mut ctx.accounts.recovery;
+        
+        let approvals = recovery.support(&guardian, Clock::get()?.unix_timestamp)?;
+        
+        // Emit event
+        emit!(RecoverySupported {
+            wallet: recovery.wallet,
+            guardian,
+            new_owner: recovery.new_owner,
+            approvals: approvals as u8,
+            executable_at: recovery.executable_at,
+        });
+        
+        msg!("Recovery supported by {} ({} approvals)", guardian, approvals);
+        
+        Ok(())
+    }
+
+    /// Cancel the recovery in progress (owner only, any time before it executes)
+    pub fn veto_recovery(ctx: Context<UpdateRecovery>) -> Result<()> {
+        // Check ownership
+        require!(
+            ctx.accounts.owner.key() == ctx.accounts.mm_wallet.owner,
+            MmWalletError::Unauthorized
+        );
+        
+        let recovery = &mut ctx.accounts.recovery;
+        require!(recovery.is_active(), MmWalletError::NoRecoveryInProgress);
+        
+        let new_owner = recovery.new_owner;
+        recovery.clear();
+        
+        // Emit event
+        emit!(RecoveryVetoed {
+            wallet: recovery.wallet,
+            owner: ctx.accounts.owner.key(),
+            new_owner,
+        });
+        
+        msg!("Recovery towards {} vetoed", new_owner);
+        
+        Ok(())
+    }
+
+    /// Rotate ownership once a backed recovery has outlived its challenge period (permissionless)
+    pub fn execute_recovery(ctx: Context<ExecuteRecovery>) -> Result<()> {
+        let recovery = &mut ctx.accounts.recovery;
+        require!(
+            recovery.is_ready(Clock::get()?.unix_timestamp),
+            MmWalletError::RecoveryNotReady
+        );
+        
+        let wallet = &mut ctx.accounts.mm_wallet;
+        let old_owner = wallet.owner;
+        let new_owner = recovery.new_owner;
+        
+        wallet.owner = new_owner;
+        wallet.pending_owner = Pubkey::default();
+        recovery.clear();
+        
+        // Emit events
+        emit!(RecoveryExecuted {
+            wallet: wallet.key(),
+            old_owner,
+            new_owner,
+        });
+        emit!(OwnershipTransferred {
+            wallet: wallet.key(),
+            old_owner,
+            new_owner,
+        });
+        
+        msg!("Ownership recovered from {} to {}", old_owner, new_owner);
+        
+        Ok(())
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════════
+    // OPERATOR REGISTRY
+    // ═══════════════════════════════════════════════════════════════════════════
+
+    /// Create the wallet's operator registry
+    /// 
+    /// From then on operator calls must pass the registry, the legacy operator included.
+    pub fn init_operator_registry(ctx: Context<InitOperatorRegistry>) -> Result<()> {
+        // Check ownership (or an approved multisig proposal)
+        check_owner_action(
+            &ctx.accounts.mm_wallet,
+            &ctx.accounts.owner,
+            &ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            ProposalAction::InitOperatorRegistry,
+        )?;
+        
+        let registry = &mut ctx.accounts.operator_registry;
+        
+        registry.wallet = ctx.accounts.mm_wallet.key();
+        registry.bump = *ctx.bumps.get("operator_registry").unwrap();
+        registry.operators = Vec::new();
+        registry.reserved = [0u8; 64];
+        
+        ctx.accounts.mm_wallet.operator_registry = true;
+        
+        msg!("Operator registry initialized");
+        
+        Ok(())
+    }
+
+    /// Register an operator with its roles and ceilings
+    pub fn add_operator(ctx: Context<UpdateOperatorRegistry>, entry: OperatorEntry) -> Result<()> {
+        // Check ownership (or an approved multisig proposal)
+        check_owner_action(
+            &ctx.accounts.mm_wallet,
+            &ctx.accounts.owner,
+            &ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            ProposalAction::AddOperator { entry },
+        )?;
+        
+        // Locked wallets go through queue_config_change
+        require!(
+            !ctx.accounts.mm_wallet.is_locked(Clock::get()?.unix_timestamp),
+            MmWalletError::ConfigChangeTimelocked
+        );
+        
+        ctx.accounts.operator_registry.add(entry)?;
+        
+        // Emit event
+        emit!(OperatorAdded {
+            wallet: ctx.accounts.mm_wallet.key(),
+            operator: entry.key,
+            roles: entry.roles,
+            max_slippage_bps: entry.max_slippage_bps,
+            max_trade_lamports: entry.max_trade_lamports,
+        });
+        
+        msg!("Operator added: {} (roles: {:#07b})", entry.key, entry.roles);
+        
+        Ok(())
+    }
+
+    /// Replace an operator's roles and ceilings
+    pub fn update_operator(ctx: Context<UpdateOperatorRegistry>, entry: OperatorEntry) -> Result<()> {
+        // Check ownership (or an approved multisig proposal)
+        check_owner_action(
+            &ctx.accounts.mm_wallet,
+            &ctx.accounts.owner,
+            &ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            ProposalAction::UpdateOperator { entry },
+        )?;
+        
+        // Locked wallets go through queue_config_change
+        require!(
+            !ctx.accounts.mm_wallet.is_locked(Clock::get()?.unix_timestamp),
+            MmWalletError::ConfigChangeTimelocked
+        );
+        
+        ctx.accounts.operator_registry.update(entry)?;
+        
+        // Emit event
+        emit!(OperatorUpdated {
+            wallet: ctx.accounts.mm_wallet.key(),
+            operator: entry.key,
+            roles: entry.roles,
+            max_slippage_bps: entry.max_slippage_bps,
+            max_trade_lamports: entry.max_trade_lamports,
+        });
+        
+        msg!("Operator updated: {} (roles: {:#07b})", entry.key, entry.roles);
+        
+        Ok(())
+    }
+
+    /// Remove an operator
+    pub fn remove_operator(ctx: Context<UpdateOperatorRegistry>, operator: Pubkey) -> Result<()> {
+        // Check ownership
+        require!(
+            ctx.accounts.owner.key() == ctx.accounts.mm_wallet.owner,
+            MmWalletError::Unauthorized
+        );
+        
+        ctx.accounts.operator_registry.remove(&operator)?;
+        
+        // Emit event
+        emit!(OperatorRemoved {
+            wallet: ctx.accounts.mm_wallet.key(),
+            operator,
+        });
+        
+        msg!("Operator removed: {}", operator);
+        
+        Ok(())
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════════
+    // SESSION KEYS
+    // ═══════════════════════════════════════════════════════════════════════════
+
+    /// Issue a session key that can sign trades and claims in place of an operator
+    /// 
+    /// The session expires at `expires_at` (unix time) or `expires_at_slot`, whichever
+    /// is set and comes first, and is revoked once `allowance_lamports` of trade notional
+    /// has been spent. Locked wallets queue it with `queue_config_change` instead, like
+    /// any other new operator.
+    pub fn create_session(
+        ctx: Context<CreateSession>,
+        session_key: Pubkey,
+        roles: u8,
+        expires_at: i64,
+        expires_at_slot: u64,
+        allowance_lamports: u64,
+    ) -> Result<()> {
+        // Check ownership (or an approved multisig proposal)
+        check_owner_action(
+            &ctx.accounts.mm_wallet,
+            &ctx.accounts.owner,
+            &ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            ProposalAction::CreateSession {
+                session_key,
+                roles,
+                expires_at,
+                expires_at_slot,
+                allowance_lamports,
+            },
+        )?;
+        
+        // Locked wallets go through queue_config_change
+        let clock = Clock::get()?;
+        require!(
+            !ctx.accounts.mm_wallet.is_locked(clock.unix_timestamp),
+            MmWalletError::ConfigChangeTimelocked
+        );
+        SessionKey::validate_params(roles, expires_at, expires_at_slot, allowance_lamports, &clock)?;
+        
+        let session = &mut ctx.accounts.session;
+        
+        session.wallet = ctx.accounts.mm_wallet.key();
+        session.key = session_key;
+        session.bump = *ctx.bumps.get("session").unwrap();
+        session.roles = roles;
+        session.expires_at = expires_at;
+        session.expires_at_slot = expires_at_slot;
+        session.allowance_lamports = allowance_lamports;
+        session.spent_lamports = 0;
+        session.revoked = false;
+        session.created_at = clock.unix_timestamp;
+        session.reserved = [0u8; 32];
+        
+        // Emit event
+        emit!(SessionCreated {
+            wallet: ctx.accounts.mm_wallet.key(),
+            session_key,
+            roles,
+            expires_at,
+            expires_at_slot,
+            allowance_lamports,
+        });
+        
+        msg!("Session created: {} (allowance: {} lamports)", session_key, allowance_lamports);
+        
+        Ok(())
+    }
+
+    /// Revoke a session key and reclaim its rent (also cleans up expired or exhausted sessions)
+    pub fn revoke_session(ctx: Context<RevokeSession>) -> Result<()> {
+        // Check ownership
+        require!(
+            ctx.accounts.owner.key() == ctx.accounts.mm_wallet.owner,
+            MmWalletError::Unauthorized
+        );
+        
+        let session_key = ctx.accounts.session.key;
+        
+        // Emit event
+        emit!(SessionRevoked {
+            wallet: ctx.accounts.mm_wallet.key(),
+            session_key,
+            exhausted: false,
+        });
+        
+        msg!("Session revoked: {}", session_key);
+        
+        Ok(())
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════════
+    // RISK LIMITS
+    // ═══════════════════════════════════════════════════════════════════════════
+
+    /// Create the wallet's risk limits account
+    /// 
+    /// Wallets without one (including migrated wallets) trade without limits; once
+    /// created, every trade must carry it.
+    pub fn init_risk_limits(ctx: Context<InitRiskLimits>, limits: RiskLimitsConfig) -> Result<()> {
+        // Check ownership (or an approved multisig proposal)
+        check_owner_action(
+            &ctx.accounts.mm_wallet,
+            &ctx.accounts.owner,
+            &ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            ProposalAction::UpdateRiskLimits { limits },
+        )?;
+        
+        limits.validate()?;
+        
+        let risk_limits = &mut ctx.accounts.risk_limits;
+        
+        risk_limits.wallet = ctx.accounts.mm_wallet.key();
+        risk_limits.bump = *ctx.bumps.get("risk_limits").unwrap();
+        risk_limits.limits = limits;
+        risk_limits.current_bucket = 0;
+        risk_limits.buy_buckets = [0; risk::WINDOW_BUCKETS];
+        risk_limits.sell_buckets = [0; risk::WINDOW_BUCKETS];
+        risk_limits.high_water_mark = 0;
+        risk_limits.commitment_window = 0;
+        risk_limits.commitment_base = 0;
+        risk_limits.commitment_net_sold = 0;
+        risk_limits.commitment_prev_net_sold = 0;
+        risk_limits.reserved = [0u8; 14];
+        
+        ctx.accounts.mm_wallet.risk_limits = true;
+        
+        emit_risk_limits_updated(ctx.accounts.mm_wallet.key(), &limits);
+        
+        msg!("Risk limits initialized");
+        
+        Ok(())
+    }
+
+    /// Update risk limits (0 = unlimited)
+    pub fn update_risk_limits(ctx: Context<UpdateRiskLimits>, limits: RiskLimitsConfig) -> Result<()> {
+        // Check ownership (or an approved multisig proposal)
+        check_owner_action(
+            &ctx.accounts.mm_wallet,
+            &ctx.accounts.owner,
+            &ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            ProposalAction::UpdateRiskLimits { limits },
+        )?;
+        
+        limits.validate()?;
+        
+        ctx.accounts.risk_limits.limits = limits;
+        
+        emit_risk_limits_updated(ctx.accounts.mm_wallet.key(), &limits);
+        
+        msg!("Risk limits updated");
+        
+        Ok(())
+    }
+
+    /// Resume trading after a circuit-breaker trip
+    /// 
+    /// Clears the high-water mark so it re-anchors at the next trade's equity.
+    pub fn reset_circuit_breaker(ctx: Context<UpdateRiskLimits>) -> Result<()> {
+        // Check ownership (or an approved multisig proposal)
+        check_owner_action(
+            &ctx.accounts.mm_wallet,
+            &ctx.accounts.owner,
+            &ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            ProposalAction::ResetCircuitBreaker,
+        )?;
+        
+        ctx.accounts.risk_limits.high_water_mark = 0;
+        ctx.accounts.mm_wallet.paused = false;
+        
+        msg!("Circuit breaker reset - trading resumed");
+        
+        Ok(())
+    }
+}
+
+fn emit_vesting_updated(wallet: Pubkey, vesting: &VestingSchedule) {
+    emit!(VestingUpdated {
+        wallet,
+        start: vesting.start,
+        cliff: vesting.cliff,
+        end: vesting.end,
+        locked_principal: vesting.locked_principal,
+    });
+}
+
+fn emit_token_lock_updated(wallet: Pubkey, vesting: &VestingSchedule) {
+    emit!(TokenLockUpdated {
+        wallet,
+        start: vesting.start,
+        cliff: vesting.cliff,
+        end: vesting.end,
+        locked_tokens: vesting.locked_principal,
+    });
+}
+
+/// `MmWallet::authorize_owner_action`, emitting `ProposalExecuted` when a proposal is used
+fn check_owner_action<'info>(
+    wallet: &MmWallet,
+    owner: &Signer<'info>,
+    multisig: &Option<Account<'info, OwnerMultisig>>,
+    proposal: &mut Option<Account<'info, Proposal>>,
+    action: ProposalAction,
+) -> Result<()> {
+    wallet.authorize_owner_action(&owner.key(), multisig.as_deref(), proposal.as_deref_mut(), &action)?;
+    
+    if let (true, Some(proposal)) = (wallet.multisig, proposal.as_ref()) {
+        emit!(ProposalExecuted {
+            wallet: proposal.wallet,
+            proposal: proposal.key(),
+            executor: owner.key(),
+            kind: action.kind(),
+        });
+    }
+    
+    Ok(())
+}
+
+fn emit_multisig_updated(multisig: &OwnerMultisig) {
+    emit!(MultisigUpdated {
+        wallet: multisig.wallet,
+        owners: multisig.owners.clone(),
+        threshold: multisig.threshold,
+        generation: multisig.generation,
+    });
+}
+
+fn emit_recovery_config_updated(recovery: &RecoveryConfig) {
+    emit!(RecoveryConfigUpdated {
+        wallet: recovery.wallet,
+        guardians: recovery.guardians.clone(),
+        threshold: recovery.threshold,
+        challenge_seconds: recovery.challenge_seconds,
+    });
+}
+
+fn emit_protocol_config_updated(config: &ProtocolConfig) {
+    emit!(ProtocolConfigUpdated {
+        admin: config.admin,
+        guardian: config.guardian,
+        allowed_programs: config.allowed_programs.clone(),
+        max_trade_pct: config.bounds.max_trade_pct,
+        min_slippage_bps: config.bounds.min_slippage_bps,
+        max_slippage_bps: config.bounds.max_slippage_bps,
+        treasury: config.treasury,
+        fee_bps_by_tier: config.fee_bps_by_tier,
+    });
+}
+
+fn emit_risk_limits_updated(wallet: Pubkey, limits: &RiskLimitsConfig) {
+    emit!(RiskLimitsUpdated {
+        wallet,
+        max_daily_buy_lamports: limits.max_daily_buy_lamports,
+        max_daily_sell_lamports: limits.max_daily_sell_lamports,
+        max_trade_lamports: limits.max_trade_lamports,
+        max_token_position: limits.max_token_position,
+        max_sell_tokens: limits.max_sell_tokens,
+        max_drawdown_bps: limits.max_drawdown_bps,
+    });
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// VALIDATION HELPERS
+// ═══════════════════════════════════════════════════════════════════════════════
+
+fn validate_config(config: &StrategyConfig, bounds: &ConfigBounds) -> Result<()> {
+    // Trade size and slippage within protocol bounds (default 1-50%, 10-5000 bps)
+    bounds.check(config)?;
+    
+    // Quote tolerance: 0 (default) - 20%
+    require!(
+        config.quote_tolerance_bps <= MAX_QUOTE_TOLERANCE_BPS,
+        MmWalletError::InvalidQuoteTolerance
+    );
+    
+    // Delays: min <= max
+    require!(
+        config.min_delay_secs <= config.max_delay_secs,
+        MmWalletError::InvalidDelayConfig
+    );
+    
+    Ok(())
+}
+
+/// Require the remaining account at `index` to be `expected`
+pub(crate) fn require_remaining_key(
+    accounts: &[AccountInfo],
+    index: usize,
+    expected: &Pubkey,
+    error: MmWalletError,
+) -> Result<()> {
+    let account = accounts
+        .get(index)
+        .ok_or(error!(MmWalletError::InvalidRemainingAccounts))?;
+    require_keys_eq!(*account.key, *expected, error);
+    
+    Ok(())
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// TRADE SETTLEMENT
+// ═══════════════════════════════════════════════════════════════════════════════
+
+/// PDA balances captured around a venue CPI
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BalanceSnapshot {
+    /// SOL held by the PDA (lamports, or WSOL for PumpSwap trades)
+    pub lamports: u64,
+    
+    /// Tokens held in the PDA's token account
+    pub tokens: u64,
+}
+
+impl BalanceSnapshot {
+    pub fn capture(pda_wallet: &AccountInfo, pda_token_account: &TokenAccount) -> Self {
+        Self {
+            lamports: pda_wallet.lamports(),
+            tokens: pda_token_account.amount,
+        }
+    }
+    
+    /// Snapshot for PumpSwap, which settles SOL through the PDA's WSOL account
+    pub fn capture_wrapped(pda_quote_account: &TokenAccount, pda_token_account: &TokenAccount) -> Self {
+        Self {
+            lamports: pda_quote_account.amount,
+            tokens: pda_token_account.amount,
+        }
+    }
+    
+    /// Everything the PDA holds: native SOL plus WSOL, and tokens
+    pub fn capture_holdings(
+        pda_wallet: &AccountInfo,
+        pda_token_account: &TokenAccount,
+        pda_quote_account: Option<&TokenAccount>,
+    ) -> Self {
+        Self {
+            lamports: pda_wallet
+                .lamports()
+                .saturating_add(pda_quote_account.map_or(0, |a| a.amount)),
+            tokens: pda_token_account.amount,
+        }
+    }
+    
+    /// SOL plus tokens valued at `mark_price` (lamports per token, scaled by PRICE_SCALE)
+    pub fn equity(&self, mark_price: u64) -> u64 {
+        let token_value = (self.tokens as u128) * (mark_price as u128) / PRICE_SCALE;
+        (self.lamports as u128 + token_value).min(u64::MAX as u128) as u64
+    }
+    
+    /// Realized (amount_in, amount_out) between this snapshot and `after`
+    /// Buys spend SOL for tokens, sells spend tokens for SOL
+    pub fn realized(&self, after: &BalanceSnapshot, is_buy: bool) -> (u64, u64) {
+        if is_buy {
+            (
+                self.lamports.saturating_sub(after.lamports),
+                after.tokens.saturating_sub(self.tokens),
+            )
+        } else {
+            (
+                self.tokens.saturating_sub(after.tokens),
+                after.lamports.saturating_sub(self.lamports),
+            )
+        }
+    }
+}
+
+/// Check measured trade deltas against the authorized input and the slippage floor.
+/// The floor comes from `min_output_from_quote`: the on-chain quote less the owner's `slippage_bps`.
+pub(crate) fn verify_realized(realized_in: u64, realized_out: u64, max_in: u64, min_out: u64) -> Result<()> {
+    require!(realized_in <= max_in, MmWalletError::RealizedInputExceedsMax);
+    require!(
+        realized_out > 0 && realized_out >= min_out,
+        MmWalletError::RealizedOutputBelowMin
+    );
+    
+    Ok(())
+}
+
+/// Measured result of a venue trade
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Fill {
+    pub venue: Venue,
+    pub side: TradeSide,
+    
+    /// Requested input (lamports for buys, tokens for sells)
+    pub amount_in: u64,
+    
+    /// Slippage floor passed to the venue
+    pub min_amount_out: u64,
+    
+    /// Input actually spent, measured from PDA balances
+    pub realized_in: u64,
+    
+    /// Output actually received, measured from PDA balances
+    pub realized_out: u64,
+    
+    /// Venue spot price after the trade (lamports per token, scaled by PRICE_SCALE)
+    pub mark_price: u64,
+}
+
+impl Fill {
+    /// SOL leg of the trade (lamports spent on buys, received on sells)
+    pub fn sol_amount(&self) -> u64 {
+        match self.side {
+            TradeSide::Buy => self.realized_in,
+            TradeSide::Sell => self.realized_out,
+        }
+    }
+    
+    /// Token leg of the trade (tokens received on buys, spent on sells)
+    pub fn token_amount(&self) -> u64 {
+        match self.side {
+            TradeSide::Buy => self.realized_out,
+            TradeSide::Sell => self.realized_in,
+        }
+    }
+}
+
+/// Build a venue instruction forwarding the remaining accounts as given
+pub(crate) fn venue_instruction(program_id: Pubkey, accounts: &[AccountInfo], data: Vec<u8>) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: accounts.iter().map(|a| {
+            if a.is_writable {
+                AccountMeta::new(*a.key, a.is_signer)
+            } else {
+                AccountMeta::new_readonly(*a.key, a.is_signer)
+            }
+        }).collect(),
+        data,
+    }
+}
+
+/// Shared body of `execute_buy`/`execute_sell`
+fn execute_bonding_curve_trade<'info>(
+    ctx: Context<'_, '_, '_, 'info, ExecuteTrade<'info>>,
+    side: TradeSide,
+    amount_in: u64,
+    expected_out: u64,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    
+    // ═══ AUTHORIZATION ═══
+    let permissions = caller_permissions(
+        &ctx.accounts.mm_wallet,
+        &ctx.accounts.caller.key(),
+        ctx.accounts.operator_registry.as_deref(),
+        ctx.accounts.session.as_deref(),
+        &clock,
+    )?;
+    permissions.require(side.role())?;
+    
+    ctx.accounts.mm_wallet.check_trade_allowed(clock.unix_timestamp)?;
+    
+    // ═══ PROGRAM VALIDATION ═══
+    require!(
+        ctx.accounts.target_program.key() == PUMP_FUN_PROGRAM,
+        MmWalletError::InvalidProgram
+    );
+    ctx.accounts.protocol_config.check_trade(&ctx.accounts.mm_wallet.config, &PUMP_FUN_PROGRAM)?;
+    
+    let accounts = &mut *ctx.accounts;
+    let fill = pump_fun::execute_trade(
+        CurveTradeAccounts {
+            pda_wallet: &accounts.pda_wallet,
+            pda_token_account: &mut accounts.pda_token_account,
+            bonding_curve: &accounts.bonding_curve,
+            pump_global: &accounts.pump_global,
+            remaining: ctx.remaining_accounts,
+        },
+        &accounts.mm_wallet,
+        side,
+        amount_in,
+        expected_out,
+        permissions.max_slippage_bps,
+    )?;
+    permissions.check_fill(&fill)?;
+    charge_session(accounts.mm_wallet.key(), accounts.session.as_mut(), &fill)?;
+    charge_protocol_fee(
+        &mut accounts.mm_wallet,
+        &accounts.protocol_config,
+        &accounts.pda_wallet,
+        &accounts.treasury,
+        None,
+        &fill,
+        clock.unix_timestamp,
+    )?;
+    
+    let holdings = BalanceSnapshot::capture_holdings(&accounts.pda_wallet, &accounts.pda_token_account, None);
+    settle_trade(&mut accounts.mm_wallet, accounts.risk_limits.as_mut(), &fill, holdings, clock.unix_timestamp)
+}
+
+/// Resolve the caller's permissions; a session key (bound to the caller by its seeds)
+/// stands in for an operator
+fn caller_permissions(
+    wallet: &MmWallet,
+    caller: &Pubkey,
+    registry: Option<&OperatorRegistry>,
+    session: Option<&SessionKey>,
+    clock: &Clock,
+) -> Result<Permissions> {
+    match session {
+        Some(session) => session.permissions(clock),
+        None => wallet.permissions(caller, registry),
+    }
+}
+
+/// Charge a fill's SOL leg against the session allowance, if the trade used one
+fn charge_session(wallet: Pubkey, session: Option<&mut Account<SessionKey>>, fill: &Fill) -> Result<()> {
+    if let Some(session) = session {
+        if session.consume(fill.sol_amount())? {
+            emit!(SessionRevoked {
+                wallet,
+                session_key: session.key,
+                exhausted: true,
+            });
+            
+            msg!("Session {} exhausted its allowance - revoked", session.key);
+        }
+    }
+    
+    Ok(())
+}
+
+/// WSOL accounts a PumpSwap sell pays its protocol fee through
+struct QuoteFeeAccounts<'a, 'info> {
+    pda_quote_account: &'a mut Account<'info, TokenAccount>,
+    treasury_quote_account: Option<&'a Account<'info, TokenAccount>>,
+    token_program: &'a Program<'info, Token>,
+}
+
+/// Transfer the protocol fee on a fill's SOL leg from the PDA to the treasury
+/// 
+/// PumpSwap sell proceeds land in the PDA's WSOL account, so those fills pay in WSOL to
+/// the treasury's WSOL account. Every other fill pays from the PDA's native lamports,
+/// which must stay above the rent reserve.
+fn charge_protocol_fee<'info>(
+    wallet: &mut Account<'info, MmWallet>,
+    protocol_config: &ProtocolConfig,
+    pda_wallet: &AccountInfo<'info>,
+    treasury: &AccountInfo<'info>,
+    quote: Option<QuoteFeeAccounts<'_, 'info>>,
+    fill: &Fill,
+    now: i64,
+) -> Result<()> {
+    let notional = fill.sol_amount();
+    let fee = protocol_config.protocol_fee(wallet.fee_tier, notional)?;
+    if fee == 0 {
+        return Ok(());
+    }
+    
+    if fill.venue == Venue::PumpSwap && fill.side == TradeSide::Sell {
+        let quote = quote.ok_or(error!(MmWalletError::TreasuryQuoteAccountRequired))?;
+        let treasury_quote_account = quote
+            .treasury_quote_account
+            .ok_or(error!(MmWalletError::TreasuryQuoteAccountRequired))?;
+        
+        let seed_owner = wallet.seed_owner;
+        let nonce_bytes = wallet.nonce.to_le_bytes();
+        let seeds = &[
+            b"mm_wallet",
+            seed_owner.as_ref(),
+            nonce_bytes.as_ref(),
+            &[wallet.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+        
+        token::transfer(
+            CpiContext::new_with_signer(
+                quote.token_program.to_account_info(),
+                Transfer {
+                    from: quote.pda_quote_account.to_account_info(),
+                    to: treasury_quote_account.to_account_info(),
+                    authority: pda_wallet.clone(),
+                },
+                signer_seeds,
+            ),
+            fee,
+        )?;
+        quote.pda_quote_account.reload()?;
+    } else {
+        require!(
+            pda_wallet.lamports().saturating_sub(MIN_RENT_RESERVE) >= fee,
+            MmWalletError::BelowRentReserve
+        );
+        
+        **pda_wallet.try_borrow_mut_lamports()? -= fee;
+        **treasury.try_borrow_mut_lamports()? += fee;
+    }
+    
+    wallet.total_protocol_fees = wallet.total_protocol_fees.saturating_add(fee);
+    
+    emit!(ProtocolFeeCharged {
+        wallet: wallet.key(),
+        treasury: treasury.key(),
+        fee_tier: wallet.fee_tier,
+        notional,
+        amount: fee,
+        timestamp: now,
+    });
+    
+    msg!("Protocol fee: {} lamports", fee);
+    
+    Ok(())
+}
+
+/// Enforce the token lock and risk limits, record a verified fill on the wallet and emit `TradeExecuted`
+/// 
+/// `holdings` are the PDA's balances after the trade. A drawdown past the owner's
+/// threshold does not fail the trade; it pauses the wallet instead. Without a
+/// `RiskLimits` account no limits apply, unless the wallet has one or a commitment.
+fn settle_trade(
+    wallet: &mut Account<MmWallet>,
+    mut risk_limits: Option<&mut Account<RiskLimits>>,
+    fill: &Fill,
+    holdings: BalanceSnapshot,
+    now: i64,
+) -> Result<()> {
+    // ═══ TOKEN LOCK ═══
+    if fill.side == TradeSide::Sell {
+        wallet.check_token_lock(holdings.tokens, now)?;
+    }
+    
+    // ═══ RISK LIMITS ═══
+    match risk_limits.as_deref_mut() {
+        Some(risk_limits) => {
+            risk_limits.record_fill(fill, holdings.tokens, now)?;
+            if wallet.commitment_active(now) {
+                risk_limits.record_commitment(fill, holdings.tokens, wallet.commitment_bps, now)?;
+            }
+        }
+        None => require!(
+            !wallet.risk_limits && !wallet.commitment_active(now),
+            MmWalletError::RiskLimitsRequired
+        ),
+    }
+    
+    // ═══ UPDATE STATS ═══
+    wallet.record_fill(fill, now);
+    
+    // Emit event
+    emit!(TradeExecuted {
+        wallet: wallet.key(),
+        trade_type: fill.side as u8,
+        venue: fill.venue as u8,
+        amount_in: fill.amount_in,
+        min_amount_out: fill.min_amount_out,
+        realized_in: fill.realized_in,
+        realized_out: fill.realized_out,
+        token_inventory: wallet.token_inventory,
+        avg_entry_price: wallet.avg_entry_price(),
+        realized_pnl: wallet.realized_pnl,
+        timestamp: now,
+    });
+    
+    msg!(
+        "Executed {:?} on {:?}: {} in, {} out (min: {})",
+        fill.side,
+        fill.venue,
+        fill.realized_in,
+        fill.realized_out,
+        fill.min_amount_out
+    );
+    
+    // ═══ CIRCUIT BREAKER ═══
+    let Some(risk_limits) = risk_limits else {
+        return Ok(());
+    };
+    let equity = holdings.equity(fill.mark_price);
+    if let Some(drawdown_bps) = risk_limits.check_drawdown(equity) {
+        wallet.paused = true;
+        
+        emit!(CircuitBreakerTripped {
+            wallet: wallet.key(),
+            equity,
+            high_water_mark: risk_limits.high_water_mark,
+            drawdown_bps,
+            timestamp: now,
+        });
+        
+        msg!("Circuit breaker tripped: {} bps drawdown - trading paused", drawdown_bps);
+    }
+    
+    Ok(())
+}
+
+/// Lower the high-water mark by SOL leaving the PDA outside of a trade
+/// (withdrawals and fee payouts), so it isn't mistaken for drawdown
+fn record_withdrawal(wallet: &MmWallet, risk_limits: Option<&mut Account<RiskLimits>>, amount: u64) -> Result<()> {
+    match risk_limits {
+        Some(risk_limits) => risk_limits.record_withdrawal(amount),
+        None => require!(!wallet.risk_limits, MmWalletError::RiskLimitsRequired),
+    }
+    
+    Ok(())
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// ACCOUNT CONTEXTS
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[derive(Accounts)]
+#[instruction(nonce: u64)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = MmWallet::SIZE,
+        seeds = [b"mm_wallet", owner.key().as_ref(), &nonce.to_le_bytes()],
+        bump
+    )]
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    /// CHECK: PDA for holding SOL
+    #[account(
+        seeds = [b"mm_wallet", owner.key().as_ref(), &nonce.to_le_bytes()],
+        bump
+    )]
+    pub pda_wallet: AccountInfo<'info>,
+    
+    /// Protocol bounds for the initial config (built-in bounds when omitted)
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Option<Account<'info, ProtocolConfig>>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(mut)]
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    /// CHECK: PDA for holding SOL
+    #[account(
+        mut,
+        seeds = [b"mm_wallet", mm_wallet.seed_owner.as_ref(), &mm_wallet.nonce.to_le_bytes()],
+        bump = mm_wallet.bump
+    )]
+    pub pda_wallet: AccountInfo<'info>,
+    
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(mut)]
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    /// CHECK: PDA for holding SOL
+    #[account(
+        mut,
+        seeds = [b"mm_wallet", mm_wallet.seed_owner.as_ref(), &mm_wallet.nonce.to_le_bytes()],
+        bump = mm_wallet.bump
+    )]
+    pub pda_wallet: AccountInfo<'info>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    /// CHECK: Must be same as owner (validated in handler)
+    #[account(mut)]
+    pub destination: AccountInfo<'info>,
+    
+    pub system_program: Program<'info, System>,
+    
+    /// Owner set, for wallets with multisig ownership
+    #[account(seeds = [b"multisig", mm_wallet.key().as_ref()], bump = multisig.bump)]
+    pub multisig: Option<Account<'info, OwnerMultisig>>,
+    
+    /// Proposal approving this exact action, for wallets with multisig ownership
+    #[account(
+        mut,
+        seeds = [b"proposal", mm_wallet.key().as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Option<Account<'info, Proposal>>,
+    
+    /// Fee split, whose undistributed fees withdrawals leave in place
+    #[account(seeds = [b"fee_split", mm_wallet.key().as_ref()], bump = fee_split.bump)]
+    pub fee_split: Option<Account<'info, FeeSplit>>,
+    
+    /// Risk limits, whose high-water mark drops by the amount withdrawn (required once initialized)
+    #[account(
+        mut,
+        seeds = [b"risk_limits", mm_wallet.key().as_ref()],
+        bump = risk_limits.bump
+    )]
+    pub risk_limits: Option<Account<'info, RiskLimits>>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTokens<'info> {
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    /// CHECK: PDA for signing
+    #[account(
+        seeds = [b"mm_wallet", mm_wallet.seed_owner.as_ref(), &mm_wallet.nonce.to_le_bytes()],
+        bump = mm_wallet.bump
+    )]
+    pub pda_wallet: AccountInfo<'info>,
+    
+    /// PDA's token account for the wallet's mint (the token lock is checked against it)
+    #[account(
+        mut,
+        constraint = pda_token_account.mint == token_mint.key() @ MmWalletError::TokenMintMismatch,
+        constraint = pda_token_account.owner == pda_wallet.key() @ MmWalletError::InvalidTokenAccount
+    )]
+    pub pda_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = owner_token_account.mint == token_mint.key() @ MmWalletError::TokenMintMismatch
+    )]
+    pub owner_token_account: Account<'info, TokenAccount>,
+    
+    pub token_mint: Account<'info, Mint>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    
+    /// Owner set, for wallets with multisig ownership
+    #[account(seeds = [b"multisig", mm_wallet.key().as_ref()], bump = multisig.bump)]
+    pub multisig: Option<Account<'info, OwnerMultisig>>,
+    
+    /// Proposal approving this exact action, for wallets with multisig ownership
+    #[account(
+        mut,
+        seeds = [b"proposal", mm_wallet.key().as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Option<Account<'info, Proposal>>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteTrade<'info> {
+    #[account(mut)]
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    /// Risk limits enforced on the fill (required once initialized)
+    #[account(
+        mut,
+        seeds = [b"risk_limits", mm_wallet.key().as_ref()],
+        bump = risk_limits.bump
+    )]
+    pub risk_limits: Option<Account<'info, RiskLimits>>,
+    
+    /// Operators other than the owner and legacy operator (required once initialized)
+    #[account(
+        seeds = [b"operators", mm_wallet.key().as_ref()],
+        bump = operator_registry.bump
+    )]
+    pub operator_registry: Option<Account<'info, OperatorRegistry>>,
+    
+    /// Session key signing in place of an operator (bound to `caller`)
+    #[account(
+        mut,
+        seeds = [b"session", mm_wallet.key().as_ref(), caller.key().as_ref()],
+        bump = session.bump
+    )]
+    pub session: Option<Account<'info, SessionKey>>,
+    
+    /// Protocol-wide kill switch, program allowlist and config bounds
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    
+    /// CHECK: Protocol fee recipient (must be the configured treasury)
+    #[account(mut, address = protocol_config.treasury @ MmWalletError::InvalidTreasury)]
+    pub treasury: AccountInfo<'info>,
+    
+    /// CHECK: PDA for signing
+    #[account(
+        mut,
+        seeds = [b"mm_wallet", mm_wallet.seed_owner.as_ref(), &mm_wallet.nonce.to_le_bytes()],
+        bump = mm_wallet.bump
+    )]
+    pub pda_wallet: AccountInfo<'info>,
+    
+    /// PDA's token account for the wallet's mint (balance verified around the CPI)
+    #[account(
+        mut,
+        constraint = pda_token_account.mint == mm_wallet.token_mint @ MmWalletError::TokenMintMismatch,
+        constraint = pda_token_account.owner == pda_wallet.key() @ MmWalletError::InvalidTokenAccount
+    )]
+    pub pda_token_account: Account<'info, TokenAccount>,
+    
+    /// CHECK: Must be Pump.fun program
+    pub target_program: AccountInfo<'info>,
+    
+    /// CHECK: Pump.fun bonding curve (owner and discriminator validated in handler)
+    pub bonding_curve: AccountInfo<'info>,
+    
+    /// CHECK: Pump.fun Global account (address validated in handler)
+    pub pump_global: AccountInfo<'info>,
+    
+    /// Caller must be owner or an operator with the roles for the trade
+    pub caller: Signer<'info>,
+    
+    // Remaining accounts are passed to Pump.fun CPI (layout in `pump_fun::account_index`)
+}
+
+#[derive(Accounts)]
+pub struct ExecuteSwap<'info> {
+    #[account(mut)]
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    /// Risk limits enforced on the fill (required once initialized)
+    #[account(
+        mut,
+        seeds = [b"risk_limits", mm_wallet.key().as_ref()],
+        bump = risk_limits.bump
+    )]
+    pub risk_limits: Option<Account<'info, RiskLimits>>,
+    
+    /// Operators other than the owner and legacy operator (required once initialized)
+    #[account(
+        seeds = [b"operators", mm_wallet.key().as_ref()],
+        bump = operator_registry.bump
+    )]
+    pub operator_registry: Option<Account<'info, OperatorRegistry>>,
+    
+    /// Session key signing in place of an operator (bound to `caller`)
+    #[account(
+        mut,
+        seeds = [b"session", mm_wallet.key().as_ref(), caller.key().as_ref()],
+        bump = session.bump
+    )]
+    pub session: Option<Account<'info, SessionKey>>,
+    
+    /// Protocol-wide kill switch, program allowlist and config bounds
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    
+    /// CHECK: Protocol fee recipient (must be the configured treasury)
+    #[account(mut, address = protocol_config.treasury @ MmWalletError::InvalidTreasury)]
+    pub treasury: AccountInfo<'info>,
+    
+    /// Treasury's WSOL account, receiving protocol fees on sells
+    #[account(
+        mut,
+        address = get_associated_token_address(&protocol_config.treasury, &native_mint::ID) @ MmWalletError::InvalidTreasury
+    )]
+    pub treasury_quote_account: Option<Account<'info, TokenAccount>>,
+    
+    /// CHECK: PDA for signing
+    #[account(
+        mut,
+        seeds = [b"mm_wallet", mm_wallet.seed_owner.as_ref(), &mm_wallet.nonce.to_le_bytes()],
+        bump = mm_wallet.bump
+    )]
+    pub pda_wallet: AccountInfo<'info>,
+    
+    /// PDA's token account for the wallet's mint (balance verified around the CPI)
+    #[account(
+        mut,
+        constraint = pda_token_account.mint == mm_wallet.token_mint @ MmWalletError::TokenMintMismatch,
+        constraint = pda_token_account.owner == pda_wallet.key() @ MmWalletError::InvalidTokenAccount
+    )]
+    pub pda_token_account: Account<'info, TokenAccount>,
+    
+    /// PDA's WSOL account (PumpSwap quote side, balance verified around the CPI)
+    #[account(
+        mut,
+        constraint = pda_quote_account.mint == native_mint::ID @ MmWalletError::InvalidTokenAccount,
+        constraint = pda_quote_account.owner == pda_wallet.key() @ MmWalletError::InvalidTokenAccount
+    )]
+    pub pda_quote_account: Account<'info, TokenAccount>,
+    
+    /// CHECK: PumpSwap pool (owner, discriminator and mints validated in handler)
+    pub pool: AccountInfo<'info>,
+    
+    /// Pool's token vault (validated against the pool in handler)
+    pub pool_base_token_account: Account<'info, TokenAccount>,
+    
+    /// Pool's WSOL vault (validated against the pool in handler)
+    pub pool_quote_token_account: Account<'info, TokenAccount>,
+    
+    /// CHECK: PumpSwap GlobalConfig (address validated in handler)
+    pub global_config: AccountInfo<'info>,
+    
+    /// CHECK: Must be PumpSwap program
+    pub target_program: AccountInfo<'info>,
+    
+    /// Caller must be owner or an operator with the roles for the trade
+    pub caller: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    
+    // Remaining accounts are passed to PumpSwap CPI (layout in `pumpswap::account_index`)
+}
+
+#[derive(Accounts)]
+pub struct ExecuteRoutedTrade<'info> {
+    #[account(mut)]
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    /// Risk limits enforced on the fill (required once initialized)
+    #[account(
+        mut,
+        seeds = [b"risk_limits", mm_wallet.key().as_ref()],
+        bump = risk_limits.bump
+    )]
+    pub risk_limits: Option<Account<'info, RiskLimits>>,
+    
+    /// Operators other than the owner and legacy operator (required once initialized)
+    #[account(
+        seeds = [b"operators", mm_wallet.key().as_ref()],
+        bump = operator_registry.bump
+    )]
+    pub operator_registry: Option<Account<'info, OperatorRegistry>>,
+    
+    /// Session key signing in place of an operator (bound to `caller`)
+    #[account(
+        mut,
+        seeds = [b"session", mm_wallet.key().as_ref(), caller.key().as_ref()],
+        bump = session.bump
+    )]
+    pub session: Option<Account<'info, SessionKey>>,
+    
+    /// Protocol-wide kill switch, program allowlist and config bounds
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    
+    /// CHECK: Protocol fee recipient (must be the configured treasury)
+    #[account(mut, address = protocol_config.treasury @ MmWalletError::InvalidTreasury)]
+    pub treasury: AccountInfo<'info>,
+    
+    /// Treasury's WSOL account, receiving protocol fees on PumpSwap sells
+    #[account(
+        mut,
+        address = get_associated_token_address(&protocol_config.treasury, &native_mint::ID) @ MmWalletError::InvalidTreasury
+    )]
+    pub treasury_quote_account: Option<Account<'info, TokenAccount>>,
+    
+    /// CHECK: PDA for signing
+    #[account(
+        mut,
+        seeds = [b"mm_wallet", mm_wallet.seed_owner.as_ref(), &mm_wallet.nonce.to_le_bytes()],
+        bump = mm_wallet.bump
+    )]
+    pub pda_wallet: AccountInfo<'info>,
+    
+    /// PDA's token account for the wallet's mint (balance verified around the CPI)
+    #[account(
+        mut,
+        constraint = pda_token_account.mint == mm_wallet.token_mint @ MmWalletError::TokenMintMismatch,
+        constraint = pda_token_account.owner == pda_wallet.key() @ MmWalletError::InvalidTokenAccount
+    )]
+    pub pda_token_account: Account<'info, TokenAccount>,
+    
+    /// CHECK: Pump.fun bonding curve, read for its `complete` flag (validated in handler)
+    pub bonding_curve: AccountInfo<'info>,
+    
+    /// CHECK: Pump.fun Global account (bonding-curve route only, validated in handler)
+    pub pump_global: Option<AccountInfo<'info>>,
+    
+    /// PDA's WSOL account (PumpSwap route only)
+    #[account(
+        mut,
+        constraint = pda_quote_account.mint == native_mint::ID @ MmWalletError::InvalidTokenAccount,
+        constraint = pda_quote_account.owner == pda_wallet.key() @ MmWalletError::InvalidTokenAccount
+    )]
+    pub pda_quote_account: Option<Account<'info, TokenAccount>>,
+    
+    /// CHECK: PumpSwap pool (PumpSwap route only, validated in handler)
+    pub pool: Option<AccountInfo<'info>>,
+    
+    /// Pool's token vault (PumpSwap route only, validated against the pool in handler)
+    pub pool_base_token_account: Option<Account<'info, TokenAccount>>,
+    
+    /// Pool's WSOL vault (PumpSwap route only, validated against the pool in handler)
+    pub pool_quote_token_account: Option<Account<'info, TokenAccount>>,
+    
+    /// CHECK: PumpSwap GlobalConfig (PumpSwap route only, validated in handler)
+    pub global_config: Option<AccountInfo<'info>>,
+    
+    /// Caller must be owner or an operator with the roles for the trade
+    pub caller: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    
+    // Remaining accounts are passed to the routed venue's CPI
+}
+
+#[derive(Accounts)]
+pub struct ClaimFees<'info> {
+    #[account(mut)]
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    /// CHECK: PDA for signing
+    #[account(
+        mut,
+        seeds = [b"mm_wallet", mm_wallet.seed_owner.as_ref(), &mm_wallet.nonce.to_le_bytes()],
+        bump = mm_wallet.bump
+    )]
+    pub pda_wallet: AccountInfo<'info>,
+    
+    /// Operators other than the owner and legacy operator (required once initialized)
+    #[account(
+        seeds = [b"operators", mm_wallet.key().as_ref()],
+        bump = operator_registry.bump
+    )]
+    pub operator_registry: Option<Account<'info, OperatorRegistry>>,
+    
+    /// Session key signing in place of an operator (bound to `caller`)
+    #[account(
+        seeds = [b"session", mm_wallet.key().as_ref(), caller.key().as_ref()],
+        bump = session.bump
+    )]
+    pub session: Option<Account<'info, SessionKey>>,
+    
+    /// Protocol-wide kill switch, program allowlist and config bounds
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    
+    /// Caller must be owner or an operator with the claim role
+    pub caller: Signer<'info>,
+    
+    // Remaining accounts are passed to Pump.fun CPI
+}
+
+#[derive(Accounts)]
+pub struct ClaimSwapFees<'info> {
+    #[account(mut)]
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    /// CHECK: PDA for signing, receives the unwrapped fees
+    #[account(
+        mut,
+        seeds = [b"mm_wallet", mm_wallet.seed_owner.as_ref(), &mm_wallet.nonce.to_le_bytes()],
+        bump = mm_wallet.bump
+    )]
+    pub pda_wallet: AccountInfo<'info>,
+    
+    /// PDA's WSOL account (the PumpSwap coin creator token account)
+    #[account(
+        mut,
+        address = get_associated_token_address(&pda_wallet.key(), &native_mint::ID) @ MmWalletError::InvalidTokenAccount
+    )]
+    pub pda_quote_account: Account<'info, TokenAccount>,
+    
+    /// CHECK: WSOL mint
+    #[account(address = native_mint::ID)]
+    pub quote_mint: AccountInfo<'info>,
+    
+    /// Protocol-wide kill switch and program allowlist
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    
+    /// Anyone (typically a keeper); fronts the WSOL account rent and is refunded
+    #[account(mut)]
+    pub caller: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    
+    // Remaining accounts are passed to the PumpSwap CPI (layout in `pumpswap::creator_fee_index`)
+}
+
+#[derive(Accounts)]
+pub struct InitFeeSplit<'info> {
+    #[account(mut)]
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = FeeSplit::SIZE,
+        seeds = [b"fee_split", mm_wallet.key().as_ref()],
+        bump
+    )]
+    pub fee_split: Account<'info, FeeSplit>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    
+    /// Owner set, for wallets with multisig ownership
+    #[account(seeds = [b"multisig", mm_wallet.key().as_ref()], bump = multisig.bump)]
+    pub multisig: Option<Account<'info, OwnerMultisig>>,
+    
+    /// Proposal approving this exact action, for wallets with multisig ownership
+    #[account(
+        mut,
+        seeds = [b"proposal", mm_wallet.key().as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Option<Account<'info, Proposal>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateFeeSplit<'info> {
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    #[account(
+        mut,
+        seeds = [b"fee_split", mm_wallet.key().as_ref()],
+        bump = fee_split.bump
+    )]
+    pub fee_split: Account<'info, FeeSplit>,
+    
+    pub owner: Signer<'info>,
+    
+    /// Owner set, for wallets with multisig ownership
+    #[account(seeds = [b"multisig", mm_wallet.key().as_ref()], bump = multisig.bump)]
+    pub multisig: Option<Account<'info, OwnerMultisig>>,
+    
+    /// Proposal approving this exact action, for wallets with multisig ownership
+    #[account(
+        mut,
+        seeds = [b"proposal", mm_wallet.key().as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Option<Account<'info, Proposal>>,
+}
+
+#[derive(Accounts)]
+pub struct DistributeFees<'info> {
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    #[account(
+        mut,
+        seeds = [b"fee_split", mm_wallet.key().as_ref()],
+        bump = fee_split.bump
+    )]
+    pub fee_split: Account<'info, FeeSplit>,
+    
+    /// CHECK: PDA paying out the fees
+    #[account(
+        mut,
+        seeds = [b"mm_wallet", mm_wallet.seed_owner.as_ref(), &mm_wallet.nonce.to_le_bytes()],
+        bump = mm_wallet.bump
+    )]
+    pub pda_wallet: AccountInfo<'info>,
+    
+    /// Risk limits, whose high-water mark drops by the fees paid out (required once initialized)
+    #[account(
+        mut,
+        seeds = [b"risk_limits", mm_wallet.key().as_ref()],
+        bump = risk_limits.bump
+    )]
+    pub risk_limits: Option<Account<'info, RiskLimits>>,
+    
+    // Remaining accounts are the fee split recipients, writable, in table order
+}
+
+#[derive(Accounts)]
+pub struct CreateToken<'info> {
+    #[account(mut)]
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    /// CHECK: PDA for signing
+    #[account(
+        mut,
+        seeds = [b"mm_wallet", mm_wallet.seed_owner.as_ref(), &mm_wallet.nonce.to_le_bytes()],
+        bump = mm_wallet.bump
+    )]
+    pub pda_wallet: AccountInfo<'info>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    /// Owner set, for wallets with multisig ownership
+    #[account(seeds = [b"multisig", mm_wallet.key().as_ref()], bump = multisig.bump)]
+    pub multisig: Option<Account<'info, OwnerMultisig>>,
+    
+    /// Proposal approving this exact action, for wallets with multisig ownership
+    #[account(
+        mut,
+        seeds = [b"proposal", mm_wallet.key().as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Option<Account<'info, Proposal>>,
+    
+    // Remaining accounts are passed to Pump.fun CPI
+}
+
+#[derive(Accounts)]
+pub struct SetTokenMint<'info> {
+    #[account(mut)]
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    /// Validated by Anchor's Mint type
+    pub token_mint_account: Account<'info, Mint>,
+    
+    pub owner: Signer<'info>,
+    
+    /// Owner set, for wallets with multisig ownership
+    #[account(seeds = [b"multisig", mm_wallet.key().as_ref()], bump = multisig.bump)]
+    pub multisig: Option<Account<'info, OwnerMultisig>>,
+    
+    /// Proposal approving this exact action, for wallets with multisig ownership
+    #[account(
+        mut,
+        seeds = [b"proposal", mm_wallet.key().as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Option<Account<'info, Proposal>>,
+}
+
+#[derive(Accounts)]
+pub struct OwnerAction<'info> {
+    #[account(mut)]
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    /// Owner, or a multisig owner submitting an approved proposal
+    pub owner: Signer<'info>,
+    
+    /// Owner set, for wallets with multisig ownership
+    #[account(seeds = [b"multisig", mm_wallet.key().as_ref()], bump = multisig.bump)]
+    pub multisig: Option<Account<'info, OwnerMultisig>>,
+    
+    /// Proposal approving this exact action, for wallets with multisig ownership
+    #[account(
+        mut,
+        seeds = [b"proposal", mm_wallet.key().as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Option<Account<'info, Proposal>>,
+}
+
+#[derive(Accounts)]
+pub struct InitMultisig<'info> {
+    #[account(mut)]
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = OwnerMultisig::SIZE,
+        seeds = [b"multisig", mm_wallet.key().as_ref()],
+        bump
+    )]
+    pub multisig: Account<'info, OwnerMultisig>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMultisig<'info> {
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    #[account(
+        mut,
+        seeds = [b"multisig", mm_wallet.key().as_ref()],
+        bump = multisig.bump
+    )]
+    pub multisig: Account<'info, OwnerMultisig>,
+    
+    #[account(
+        mut,
+        seeds = [b"proposal", mm_wallet.key().as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    /// Any multisig owner
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateProposal<'info> {
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    #[account(
+        mut,
+        seeds = [b"multisig", mm_wallet.key().as_ref()],
+        bump = multisig.bump
+    )]
+    pub multisig: Account<'info, OwnerMultisig>,
+    
+    #[account(
+        init,
+        payer = proposer,
+        space = Proposal::SIZE,
+        seeds = [b"proposal", mm_wallet.key().as_ref(), &multisig.proposal_count.to_le_bytes()],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveProposal<'info> {
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    #[account(seeds = [b"multisig", mm_wallet.key().as_ref()], bump = multisig.bump)]
+    pub multisig: Account<'info, OwnerMultisig>,
+    
+    #[account(
+        mut,
+        seeds = [b"proposal", mm_wallet.key().as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseProposal<'info> {
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [b"proposal", mm_wallet.key().as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitRecovery<'info> {
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = RecoveryConfig::SIZE,
+        seeds = [b"recovery", mm_wallet.key().as_ref()],
+        bump
+    )]
+    pub recovery: Account<'info, RecoveryConfig>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    
+    /// Owner set, for wallets with multisig ownership
+    #[account(seeds = [b"multisig", mm_wallet.key().as_ref()], bump = multisig.bump)]
+    pub multisig: Option<Account<'info, OwnerMultisig>>,
+    
+    /// Proposal approving this exact action, for wallets with multisig ownership
+    #[account(
+        mut,
+        seeds = [b"proposal", mm_wallet.key().as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Option<Account<'info, Proposal>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateRecovery<'info> {
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    #[account(
+        mut,
+        seeds = [b"recovery", mm_wallet.key().as_ref()],
+        bump = recovery.bump
+    )]
+    pub recovery: Account<'info, RecoveryConfig>,
+    
+    pub owner: Signer<'info>,
+    
+    /// Owner set, for wallets with multisig ownership
+    #[account(seeds = [b"multisig", mm_wallet.key().as_ref()], bump = multisig.bump)]
+    pub multisig: Option<Account<'info, OwnerMultisig>>,
+    
+    /// Proposal approving this exact action, for wallets with multisig ownership
+    #[account(
+        mut,
+        seeds = [b"proposal", mm_wallet.key().as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Option<Account<'info, Proposal>>,
+}
+
+#[derive(Accounts)]
+pub struct GuardianRecovery<'info> {
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    #[account(
+        mut,
+        seeds = [b"recovery", mm_wallet.key().as_ref()],
+        bump = recovery.bump
+    )]
+    pub recovery: Account<'info, RecoveryConfig>,
+    
+    /// One of `recovery.guardians` (checked in handler)
+    pub guardian: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteRecovery<'info> {
+    #[account(mut)]
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    #[account(
+        mut,
+        seeds = [b"recovery", mm_wallet.key().as_ref()],
+        bump = recovery.bump
+    )]
+    pub recovery: Account<'info, RecoveryConfig>,
+    
+    /// Anyone can execute a ready recovery
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateStrategy<'info> {
+    #[account(mut)]
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    /// Protocol bounds for the new config (built-in bounds when omitted)
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Option<Account<'info, ProtocolConfig>>,
+    
+    pub owner: Signer<'info>,
+    
+    /// Owner set, for wallets with multisig ownership
+    #[account(seeds = [b"multisig", mm_wallet.key().as_ref()], bump = multisig.bump)]
+    pub multisig: Option<Account<'info, OwnerMultisig>>,
+    
+    /// Proposal approving this exact action, for wallets with multisig ownership
+    #[account(
+        mut,
+        seeds = [b"proposal", mm_wallet.key().as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Option<Account<'info, Proposal>>,
+}
+
+#[derive(Accounts)]
+pub struct QueueConfigChange<'info> {
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = PendingConfigChange::SIZE,
+        seeds = [b"config_change", mm_wallet.key().as_ref()],
+        bump
+    )]
+    pub pending_change: Account<'info, PendingConfigChange>,
+    
+    /// Protocol bounds for a strategy change (built-in bounds when omitted)
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Option<Account<'info, ProtocolConfig>>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    
+    /// Owner set, for wallets with multisig ownership
+    #[account(seeds = [b"multisig", mm_wallet.key().as_ref()], bump = multisig.bump)]
+    pub multisig: Option<Account<'info, OwnerMultisig>>,
+    
+    /// Proposal approving this exact action, for wallets with multisig ownership
+    #[account(
+        mut,
+        seeds = [b"proposal", mm_wallet.key().as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Option<Account<'info, Proposal>>,
+}
+
+#[derive(Accounts)]
+pub struct ApplyConfigChange<'info> {
+    #[account(mut)]
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"config_change", mm_wallet.key().as_ref()],
+        bump = pending_change.bump
+    )]
+    pub pending_change: Account<'info, PendingConfigChange>,
+    
+    /// Protocol bounds for a strategy change (built-in bounds when omitted)
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Option<Account<'info, ProtocolConfig>>,
+    
+    /// Required for `AddOperator` / `UpdateOperator` changes
+    #[account(
+        mut,
+        seeds = [b"operators", mm_wallet.key().as_ref()],
+        bump = operator_registry.bump
+    )]
+    pub operator_registry: Option<Account<'info, OperatorRegistry>>,
+    
+    /// Created for `CreateSession` changes
+    #[account(
+        init,
+        payer = owner,
+        space = SessionKey::SIZE,
+        seeds = [b"session", mm_wallet.key().as_ref(), pending_change.change.session_key().as_ref()],
+        bump
+    )]
+    pub session: Option<Account<'info, SessionKey>>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    
+    /// Owner set, for wallets with multisig ownership
+    #[account(seeds = [b"multisig", mm_wallet.key().as_ref()], bump = multisig.bump)]
+    pub multisig: Option<Account<'info, OwnerMultisig>>,
+    
+    /// Proposal approving this exact action, for wallets with multisig ownership
+    #[account(
+        mut,
+        seeds = [b"proposal", mm_wallet.key().as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Option<Account<'info, Proposal>>,
+}
+
+#[derive(Accounts)]
+pub struct CancelConfigChange<'info> {
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"config_change", mm_wallet.key().as_ref()],
+        bump = pending_change.bump
+    )]
+    pub pending_change: Account<'info, PendingConfigChange>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitProtocolConfig<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = ProtocolConfig::SIZE,
+        seeds = [b"protocol_config"],
+        bump
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::MmWalletV2>,
+    
+    /// Only the program's upgrade authority can create the protocol config
+    #[account(constraint = program_data.upgrade_authority_address == Some(authority.key()) @ MmWalletError::Unauthorized)]
+    pub program_data: Account<'info, ProgramData>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateProtocolConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"protocol_config"],
+        bump = protocol_config.bump
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    
+    /// Admin, or the protocol guardian for `protocol_pause`
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetFeeTier<'info> {
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    
+    #[account(mut)]
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Pause<'info> {
+    #[account(mut)]
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    /// Operators other than the owner and legacy operator (required once initialized)
+    #[account(
+        seeds = [b"operators", mm_wallet.key().as_ref()],
+        bump = operator_registry.bump
+    )]
+    pub operator_registry: Option<Account<'info, OperatorRegistry>>,
+    
+    /// Caller must be owner or an operator with the pause role
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(session_key: Pubkey)]
+pub struct CreateSession<'info> {
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = SessionKey::SIZE,
+        seeds = [b"session", mm_wallet.key().as_ref(), session_key.as_ref()],
+        bump
+    )]
+    pub session: Account<'info, SessionKey>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    
+    /// Owner set, for wallets with multisig ownership
+    #[account(seeds = [b"multisig", mm_wallet.key().as_ref()], bump = multisig.bump)]
+    pub multisig: Option<Account<'info, OwnerMultisig>>,
+    
+    /// Proposal approving this exact action, for wallets with multisig ownership
+    #[account(
+        mut,
+        seeds = [b"proposal", mm_wallet.key().as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Option<Account<'info, Proposal>>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeSession<'info> {
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"session", mm_wallet.key().as_ref(), session.key.as_ref()],
+        bump = session.bump
+    )]
+    pub session: Account<'info, SessionKey>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GuardianPause<'info> {
+    #[account(mut)]
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    /// Must match `mm_wallet.guardian`
+    pub guardian: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitOperatorRegistry<'info> {
+    #[account(mut)]
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = OperatorRegistry::SIZE,
+        seeds = [b"operators", mm_wallet.key().as_ref()],
+        bump
+    )]
+    pub operator_registry: Account<'info, OperatorRegistry>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    
+    /// Owner set, for wallets with multisig ownership
+    #[account(seeds = [b"multisig", mm_wallet.key().as_ref()], bump = multisig.bump)]
+    pub multisig: Option<Account<'info, OwnerMultisig>>,
+    
+    /// Proposal approving this exact action, for wallets with multisig ownership
+    #[account(
+        mut,
+        seeds = [b"proposal", mm_wallet.key().as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Option<Account<'info, Proposal>>,
+}
 
 #[derive(Accounts)]
-pub struct ExecuteSwap<'info> {
-    #[account(mut)]
+pub struct UpdateOperatorRegistry<'info> {
     pub mm_wallet: Account<'info, MmWallet>,
     
-    /// CHECK: PDA for signing
     #[account(
         mut,
-        seeds = [b"mm_wallet", mm_wallet.owner.as_ref(), &mm_wallet.nonce.to_le_bytes()],
-        bump = mm_wallet.bump
+        seeds = [b"operators", mm_wallet.key().as_ref()],
+        bump = operator_registry.bump
     )]
-    pub pda_wallet: AccountInfo<'info>,
+    pub operator_registry: Account<'info, OperatorRegistry>,
     
-    /// CHECK: Must be PumpSwap program
-    pub target_program: AccountInfo<'info>,
+    pub owner: Signer<'info>,
     
-    /// Caller must be owner or authorized operator
-    pub caller: Signer<'info>,
+    /// Owner set, for wallets with multisig ownership
+    #[account(seeds = [b"multisig", mm_wallet.key().as_ref()], bump = multisig.bump)]
+    pub multisig: Option<Account<'info, OwnerMultisig>>,
     
-    // Remaining accounts are passed to PumpSwap CPI
+    /// Proposal approving this exact action, for wallets with multisig ownership
+    #[account(
+        mut,
+        seeds = [b"proposal", mm_wallet.key().as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Option<Account<'info, Proposal>>,
 }
 
 #[derive(Accounts)]
-pub struct ClaimFees<'info> {
+pub struct AcceptOwner<'info> {
+    #[account(mut)]
+    pub mm_wallet: Account<'info, MmWallet>,
+    
+    /// Must match `mm_wallet.pending_owner`
+    pub new_owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateWallet<'info> {
+    /// CHECK: v2 wallet account (owner, size and discriminator validated in handler)
+    #[account(mut)]
+    pub mm_wallet: AccountInfo<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitRiskLimits<'info> {
     #[account(mut)]
     pub mm_wallet: Account<'info, MmWallet>,
     
-    /// CHECK: PDA for signing
     #[account(
-        mut,
-        seeds = [b"mm_wallet", mm_wallet.owner.as_ref(), &mm_wallet.nonce.to_le_bytes()],
-        bump = mm_wallet.bump
+        init,
+        payer = owner,
+        space = RiskLimits::SIZE,
+        seeds = [b"risk_limits", mm_wallet.key().as_ref()],
+        bump
     )]
-    pub pda_wallet: AccountInfo<'info>,
+    pub risk_limits: Account<'info, RiskLimits>,
     
-    /// Caller must be owner or authorized operator
-    pub caller: Signer<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
     
-    // Remaining accounts are passed to Pump.fun CPI
+    pub system_program: Program<'info, System>,
+    
+    /// Owner set, for wallets with multisig ownership
+    #[account(seeds = [b"multisig", mm_wallet.key().as_ref()], bump = multisig.bump)]
+    pub multisig: Option<Account<'info, OwnerMultisig>>,
+    
+    /// Proposal approving this exact action, for wallets with multisig ownership
+    #[account(
+        mut,
+        seeds = [b"proposal", mm_wallet.key().as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Option<Account<'info, Proposal>>,
 }
 
 #[derive(Accounts)]
-pub struct CreateToken<'info> {
+pub struct UpdateRiskLimits<'info> {
     #[account(mut)]
     pub mm_wallet: Account<'info, MmWallet>,
     
-    /// CHECK: PDA for signing
     #[account(
         mut,
-        seeds = [b"mm_wallet", mm_wallet.owner.as_ref(), &mm_wallet.nonce.to_le_bytes()],
-        bump = mm_wallet.bump
+        seeds = [b"risk_limits", mm_wallet.key().as_ref()],
+        bump = risk_limits.bump
     )]
-    pub pda_wallet: AccountInfo<'info>,
+    pub risk_limits: Account<'info, RiskLimits>,
     
-    #[account(mut)]
     pub owner: Signer<'info>,
     
-    // Remaining accounts are passed to Pump.fun CPI
-}
-
-#[derive(Accounts)]
-pub struct SetTokenMint<'info> {
-    #[account(mut)]
-    pub mm_wallet: Account<'info, MmWallet>,
+    /// Owner set, for wallets with multisig ownership
+    #[account(seeds = [b"multisig", mm_wallet.key().as_ref()], bump = multisig.bump)]
+    pub multisig: Option<Account<'info, OwnerMultisig>>,
+    
+    /// Proposal approving this exact action, for wallets with multisig ownership
+    #[account(
+        mut,
+        seeds = [b"proposal", mm_wallet.key().as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Option<Account<'info, Proposal>>,
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// TESTS (Unit tests for security-critical functions)
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pump_fun::PumpFees;
+    use pumpswap::{PoolReserves, PoolState, SwapFees};
+    use risk::WINDOW_BUCKETS;
+    
+    #[test]
+    fn test_calculate_min_output() {
+        let wallet = MmWallet {
+            config: StrategyConfig {
+                slippage_bps: 1000, // 10%
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        
+        // 100 tokens with 10% slippage = 90 min
+        let result = wallet.calculate_min_output(100).unwrap();
+        assert_eq!(result, 90);
+    }
+    
+    #[test]
+    fn test_max_trade_amount() {
+        let wallet = MmWallet {
+            config: StrategyConfig {
+                trade_size_pct: 25, // 25%
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        
+        // 1000 available, 25% max = 250
+        let result = wallet.max_trade_amount(1000).unwrap();
+        assert_eq!(result, 250);
+    }
+    
+    #[test]
+    fn test_is_locked() {
+        let wallet = MmWallet {
+            lock_until: 1000,
+            ..Default::default()
+        };
+        
+        assert!(wallet.is_locked(500)); // Before lock expires
+        assert!(!wallet.is_locked(1000)); // At expiration
+        assert!(!wallet.is_locked(1500)); // After expiration
+    }
+    
+    #[test]
+    fn test_can_trade_rate_limiting() {
+        let wallet = MmWallet {
+            last_trade: 1000,
+            config: StrategyConfig {
+                min_delay_secs: 60,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        
+        assert!(!wallet.can_trade(1030)); // 30s later - too soon
+        assert!(!wallet.can_trade(1059)); // 59s later - still too soon
+        assert!(wallet.can_trade(1060)); // 60s later - OK
+        assert!(wallet.can_trade(1061)); // 61s later - OK
+    }
+    
+    #[test]
+    fn test_check_quote_deviation() {
+        let wallet = MmWallet {
+            config: StrategyConfig {
+                quote_tolerance_bps: 500, // 5%
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        
+        assert!(wallet.check_quote_deviation(1000, 1000).is_ok());
+        assert!(wallet.check_quote_deviation(950, 1000).is_ok());
+        assert!(wallet.check_quote_deviation(1050, 1000).is_ok());
+        assert!(wallet.check_quote_deviation(949, 1000).is_err());
+        assert!(wallet.check_quote_deviation(1, 1000).is_err());
+        
+        // Unset tolerance falls back to the default (2%)
+        let default_wallet = MmWallet::default();
+        assert!(default_wallet.check_quote_deviation(980, 1000).is_ok());
+        assert!(default_wallet.check_quote_deviation(979, 1000).is_err());
+        
+        // The floor follows the on-chain quote, not a lowballed expectation (5% slippage)
+        let wallet = MmWallet {
+            config: StrategyConfig { slippage_bps: 500, ..wallet.config },
+            ..Default::default()
+        };
+        assert_eq!(wallet.min_output_from_quote(950, 1000, 0).unwrap(), 950);
+        assert_eq!(wallet.min_output_from_quote(1050, 1000, 100).unwrap(), 990);
+        assert!(wallet.min_output_from_quote(900, 1000, 0).is_err());
+    }
+    
+    #[test]
+    fn test_bonding_curve_quotes() {
+        let curve = BondingCurveState {
+            virtual_token_reserves: 1_073_000_000_000_000,
+            virtual_sol_reserves: 30_000_000_000,
+            real_token_reserves: 793_100_000_000_000,
+            ..Default::default()
+        };
+        let no_fees = PumpFees::default();
+        let fees = PumpFees { fee_bps: 95, creator_fee_bps: 5 };
+        
+        // 1 SOL into 30 SOL / 1.073B token virtual reserves
+        let tokens = curve.quote_buy(1_000_000_000, &no_fees).unwrap();
+        assert_eq!(tokens, 34_612_903_225_806);
+        
+        // Fees reduce what the budget buys
+        assert!(curve.quote_buy(1_000_000_000, &fees).unwrap() < tokens);
+        
+        // Selling back along an unchanged curve returns less than 1 SOL after fees
+        let sol = curve.quote_sell(tokens, &fees).unwrap();
+        assert!(sol < curve.quote_sell(tokens, &no_fees).unwrap());
+        
+        let complete = BondingCurveState { complete: true, ..curve };
+        assert!(complete.quote_buy(1_000_000_000, &no_fees).is_err());
+    }
+    
+    #[test]
+    fn test_pumpswap_instruction_layouts() {
+        // buy(base_amount_out, max_quote_amount_in)
+        let buy = pumpswap::buy_data(7, 9);
+        assert_eq!(buy[..8], pumpswap::PUMPSWAP_BUY_DISCRIMINATOR);
+        assert_eq!(buy[8..16], 7u64.to_le_bytes());
+        assert_eq!(buy[16..24], 9u64.to_le_bytes());
+        
+        // sell(base_amount_in, min_quote_amount_out)
+        let sell = pumpswap::sell_data(7, 9);
+        assert_eq!(sell[..8], pumpswap::PUMPSWAP_SELL_DISCRIMINATOR);
+        assert_eq!(sell[8..16], 7u64.to_le_bytes());
+        assert_eq!(sell[16..24], 9u64.to_le_bytes());
+    }
+    
+    #[test]
+    fn test_pumpswap_pool_and_quotes() {
+        let mint = Pubkey::new_unique();
+        let base_vault = Pubkey::new_unique();
+        let quote_vault = Pubkey::new_unique();
+        
+        let mut data = pumpswap::POOL_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&[255, 0, 0]); // bump + index
+        data.extend_from_slice(Pubkey::new_unique().as_ref()); // creator
+        data.extend_from_slice(mint.as_ref());
+        data.extend_from_slice(native_mint::ID.as_ref());
+        data.extend_from_slice(Pubkey::new_unique().as_ref()); // lp_mint
+        data.extend_from_slice(base_vault.as_ref());
+        data.extend_from_slice(quote_vault.as_ref());
+        data.extend_from_slice(&0u64.to_le_bytes()); // lp_supply
+        data.extend_from_slice(Pubkey::default().as_ref()); // coin_creator
+        
+        let pool = PoolState::try_from_slice_checked(&data).unwrap();
+        assert_eq!(pool.base_mint, mint);
+        assert_eq!(pool.quote_mint, native_mint::ID);
+        assert_eq!(pool.pool_base_token_account, base_vault);
+        assert_eq!(pool.pool_quote_token_account, quote_vault);
+        assert!(PoolState::try_from_slice_checked(&data[..data.len() - 1]).is_err());
+        
+        let reserves = PoolReserves { base: 1_000_000, quote: 1_000_000 };
+        let no_fees = SwapFees::default();
+        let fees = SwapFees { lp_fee_bps: 20, protocol_fee_bps: 5, coin_creator_fee_bps: 5 };
+        
+        assert_eq!(reserves.quote_buy(1_000, &no_fees).unwrap(), 999);
+        assert_eq!(reserves.quote_sell(1_000, &no_fees).unwrap(), 999);
+        assert!(reserves.quote_buy(1_000, &fees).unwrap() < 999);
+        assert!(reserves.quote_sell(1_000, &fees).unwrap() < 999);
+    }
+    
+    #[test]
+    fn test_fill_legs() {
+        let buy = Fill {
+            side: TradeSide::Buy,
+            realized_in: 1_000, // lamports
+            realized_out: 50,   // tokens
+            ..Default::default()
+        };
+        assert_eq!((buy.sol_amount(), buy.token_amount()), (1_000, 50));
+        
+        let sell = Fill {
+            side: TradeSide::Sell,
+            realized_in: 50,     // tokens
+            realized_out: 1_000, // lamports
+            ..Default::default()
+        };
+        assert_eq!((sell.sol_amount(), sell.token_amount()), (1_000, 50));
+    }
+    
+    #[test]
+    fn test_record_fill_stats() {
+        let mut wallet = MmWallet::default();
+        
+        wallet.record_fill(&Fill { side: TradeSide::Buy, realized_in: 1_000, realized_out: 50, ..Default::default() }, 10);
+        wallet.record_fill(&Fill { side: TradeSide::Sell, realized_in: 20, realized_out: 450, ..Default::default() }, 20);
+        
+        assert_eq!((wallet.sol_in, wallet.sol_out), (1_000, 450));
+        assert_eq!((wallet.tokens_bought, wallet.tokens_sold), (50, 20));
+        assert_eq!(wallet.total_volume, 1_450);
+        assert_eq!(wallet.total_trades, 2);
+        assert_eq!(wallet.last_trade, 20);
+    }
+    
+    #[test]
+    fn test_cost_basis_and_pnl() {
+        let mut wallet = MmWallet::default();
+        
+        // Two buys: 100 tokens for 1_000, 100 tokens for 3_000 → avg 20/token
+        wallet.record_fill(&Fill { side: TradeSide::Buy, realized_in: 1_000, realized_out: 100, ..Default::default() }, 1);
+        wallet.record_fill(&Fill { side: TradeSide::Buy, realized_in: 3_000, realized_out: 100, ..Default::default() }, 2);
+        assert_eq!(wallet.avg_entry_price(), 20 * PRICE_SCALE as u64);
+        
+        // Sell half at 25/token → +500
+        wallet.record_fill(&Fill { side: TradeSide::Sell, realized_in: 100, realized_out: 2_500, ..Default::default() }, 3);
+        assert_eq!((wallet.token_inventory, wallet.cost_basis, wallet.realized_pnl), (100, 2_000, 500));
+        assert_eq!(wallet.avg_entry_price(), 20 * PRICE_SCALE as u64);
+        
+        // Sell the rest at 10/token → -1_000
+        wallet.record_fill(&Fill { side: TradeSide::Sell, realized_in: 100, realized_out: 1_000, ..Default::default() }, 4);
+        assert_eq!((wallet.token_inventory, wallet.cost_basis, wallet.realized_pnl), (0, 0, -500));
+        assert_eq!(wallet.avg_entry_price(), 0);
+        
+        // Untracked tokens (e.g. creator allocation) are pure profit
+        wallet.record_fill(&Fill { side: TradeSide::Sell, realized_in: 50, realized_out: 700, ..Default::default() }, 5);
+        assert_eq!((wallet.token_inventory, wallet.cost_basis, wallet.realized_pnl), (0, 0, 200));
+    }
+    
+    fn risk_limits(limits: RiskLimitsConfig) -> RiskLimits {
+        RiskLimits {
+            wallet: Pubkey::default(),
+            bump: 0,
+            limits,
+            current_bucket: 0,
+            buy_buckets: [0; WINDOW_BUCKETS],
+            sell_buckets: [0; WINDOW_BUCKETS],
+            high_water_mark: 0,
+            commitment_window: 0,
+            commitment_base: 0,
+            commitment_net_sold: 0,
+            commitment_prev_net_sold: 0,
+            reserved: [0; 14],
+        }
+    }
+    
+    #[test]
+    fn test_risk_limits_caps() {
+        let mut limits = risk_limits(RiskLimitsConfig {
+            max_daily_buy_lamports: 1_000,
+            max_daily_sell_lamports: 500,
+            max_trade_lamports: 600,
+            max_token_position: 10_000,
+            max_sell_tokens: 0,
+            max_drawdown_bps: 0,
+        });
+        let buy = |sol| Fill { side: TradeSide::Buy, realized_in: sol, realized_out: 1, ..Default::default() };
+        let sell = |sol| Fill { side: TradeSide::Sell, realized_in: 1, realized_out: sol, ..Default::default() };
+        let t = 1_700_000_000;
+        
+        // Per-trade cap
+        assert!(limits.record_fill(&buy(601), 0, t).is_err());
+        
+        // Rolling buy cap
+        assert!(limits.record_fill(&buy(600), 0, t).is_ok());
+        assert!(limits.record_fill(&buy(400), 0, t + 60).is_ok());
+        assert!(limits.clone().record_fill(&buy(1), 0, t + 120).is_err());
+        
+        // Sell cap is tracked separately
+        assert!(limits.record_fill(&sell(500), 0, t + 120).is_ok());
+        assert!(limits.clone().record_fill(&sell(1), 0, t + 120).is_err());
+        
+        // Position cap applies to buys only
+        assert!(limits.clone().record_fill(&buy(0), 10_001, t + 120).is_err());
+        assert!(limits.clone().record_fill(&sell(0), 10_001, t + 120).is_ok());
+        
+        // Buckets age out after 24h
+        assert!(limits.clone().record_fill(&buy(1), 0, t + 23 * 3600).is_err());
+        assert!(limits.record_fill(&buy(600), 0, t + 24 * 3600).is_ok());
+        assert_eq!(limits.daily_buy(), 600);
+        assert_eq!(limits.daily_sell(), 0);
+    }
+    
+    #[test]
+    fn test_sell_size_caps() {
+        let wallet = MmWallet {
+            config: StrategyConfig {
+                trade_size_pct: 25,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        
+        // 25% of a 1_000 token balance
+        assert!(wallet.check_sell_amount(250, 1_000).is_ok());
+        assert!(wallet.check_sell_amount(251, 1_000).is_err());
+        assert!(wallet.check_sell_amount(1, 0).is_err());
+        
+        // Absolute cap on tokens per sell
+        let mut limits = risk_limits(RiskLimitsConfig {
+            max_sell_tokens: 100,
+            ..Default::default()
+        });
+        let sell = |tokens| Fill { side: TradeSide::Sell, realized_in: tokens, realized_out: 1, ..Default::default() };
+        assert!(limits.record_fill(&sell(100), 0, 0).is_ok());
+        assert!(limits.record_fill(&sell(101), 0, 0).is_err());
+    }
+    
+    #[test]
+    fn test_liquidity_commitment() {
+        const DAY: i64 = risk::COMMITMENT_WINDOW_SECONDS;
+        let wallet = MmWallet {
+            lock_until: 10 * DAY,
+            commitment_bps: 1_000, // 10% of inventory per 24h
+            ..Default::default()
+        };
+        assert!(wallet.commitment_active(DAY));
+        assert!(!wallet.commitment_active(10 * DAY));
+        
+        let mut limits = risk_limits(RiskLimitsConfig::default());
+        let sell = |tokens| Fill { side: TradeSide::Sell, realized_in: tokens, realized_out: 1, ..Default::default() };
+        let buy = |tokens| Fill { side: TradeSide::Buy, realized_in: 1, realized_out: tokens, ..Default::default() };
+        
+        // 10_000 tokens when the window opens: 1_000 may be sold net
+        let now = DAY;
+        assert!(limits.record_commitment(&sell(600), 9_400, 1_000, now).is_ok());
+        assert!(limits.clone().record_commitment(&sell(500), 8_900, 1_000, now).is_err());
+        
+        // Buying back makes room again
+        let mut limits_after_buy = limits.clone();
+        assert!(limits_after_buy.record_commitment(&buy(200), 9_600, 1_000, now).is_ok());
+        assert!(limits_after_buy.record_commitment(&sell(500), 9_100, 1_000, now).is_ok());
+        
+        // Half a day into the next window, half of yesterday's sells still count
+        let later = 2 * DAY + DAY / 2;
+        assert_eq!(limits.rolling_net_sold(later), 300);
+        assert!(limits.clone().record_commitment(&sell(800), 8_600, 1_000, later).is_err());
+        assert!(limits.record_commitment(&sell(600), 8_800, 1_000, later).is_ok());
+        
+        // Stale windows drop out entirely
+        assert_eq!(limits.rolling_net_sold(5 * DAY), 0);
+    }
+    
+    #[test]
+    fn test_config_change_timelock() {
+        let mut wallet = MmWallet {
+            lock_until: 1_000,
+            ..Default::default()
+        };
+        let pending = PendingConfigChange {
+            wallet: Pubkey::default(),
+            bump: 0,
+            change: ConfigChange::Operator { operator: Pubkey::new_unique() },
+            queued_at: 0,
+            eta: CONFIG_CHANGE_DELAY_SECONDS,
+            reserved: [0; 32],
+        };
+        assert!(!pending.is_ready(CONFIG_CHANGE_DELAY_SECONDS - 1));
+        assert!(pending.is_ready(CONFIG_CHANGE_DELAY_SECONDS));
+        
+        // Validation matches the direct instructions
+        let bounds = ConfigBounds::DEFAULT;
+        let reckless = StrategyConfig { trade_size_pct: 50, slippage_bps: 5_001, ..Default::default() };
+        let clock = Clock::default();
+        assert!(ConfigChange::Strategy { strategy: wallet.strategy, config: reckless }.validate(&bounds, &clock).is_err());
+        assert!(ConfigChange::Operator { operator: Pubkey::default() }.validate(&bounds, &clock).is_err());
+        assert!(pending.change.validate(&bounds, &clock).is_ok());
+        
+        // Operator and strategy changes land on the wallet
+        pending.change.apply(&mut wallet, None).unwrap();
+        assert_ne!(wallet.operator, Pubkey::default());
+        let config = StrategyConfig { trade_size_pct: 10, slippage_bps: 100, ..Default::default() };
+        let change = ConfigChange::Strategy { strategy: wallet.strategy, config };
+        assert_eq!(change.kind(), timelock::config_change_kind::STRATEGY);
+        change.apply(&mut wallet, None).unwrap();
+        assert_eq!(wallet.config.slippage_bps, 100);
+        
+        // Registry changes need the registry
+        let entry = OperatorEntry { key: Pubkey::new_unique(), roles: ROLE_BUY, ..Default::default() };
+        let add = ConfigChange::AddOperator { entry };
+        assert!(add.apply(&mut wallet, None).is_err());
+        let mut registry = OperatorRegistry {
+            wallet: Pubkey::default(),
+            bump: 0,
+            operators: Vec::new(),
+            reserved: [0; 64],
+        };
+        add.apply(&mut wallet, Some(&mut registry)).unwrap();
+        assert!(registry.find(&entry.key).is_some());
+    }
+    
+    #[test]
+    fn test_multisig_ownership() {
+        // A DAO treasury PDA (signs via CPI) owns the wallet; it is off-curve
+        let (dao, _) = Pubkey::find_program_address(&[b"treasury"], &Pubkey::new_unique());
+        assert!(!dao.is_on_curve());
+        let mut wallet = MmWallet { owner: dao, ..Default::default() };
+        let withdraw = ProposalAction::Withdraw { amount: 1_000 };
+        
+        // Without a multisig the owner (PDA or not) signs alone
+        assert!(wallet.authorize_owner_action(&dao, None, None, &withdraw).is_ok());
+        assert!(wallet.authorize_owner_action(&Pubkey::new_unique(), None, None, &withdraw).is_err());
+        
+        // 2-of-3 with the DAO PDA as one of the owners
+        let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut multisig = OwnerMultisig {
+            wallet: Pubkey::default(),
+            bump: 0,
+            owners: Vec::new(),
+            threshold: 0,
+            generation: 0,
+            proposal_count: 0,
+            reserved: [0; 32],
+        };
+        assert!(multisig.set_owners(vec![alice, alice], 1).is_err());
+        assert!(multisig.set_owners(vec![alice, bob], 3).is_err());
+        multisig.set_owners(vec![alice, bob, dao], 2).unwrap();
+        wallet.multisig = true;
+        
+        let mut proposal = Proposal {
+            wallet: Pubkey::default(),
+            index: 0,
+            bump: 0,
+            proposer: alice,
+            action: withdraw.clone(),
+            generation: multisig.generation,
+            approvals: 0,
+            executed: false,
+            created_at: 0,
+            reserved: [0; 16],
+        };
+        assert_eq!(proposal.approve(&multisig, &alice).unwrap(), 1);
+        assert!(proposal.approve(&multisig, &alice).is_err());
+        assert!(proposal.approve(&multisig, &Pubkey::new_unique()).is_err());
+        
+        // The owner alone is no longer enough, nor is a proposal below threshold
+        assert!(wallet.authorize_owner_action(&dao, None, None, &withdraw).is_err());
+        assert!(wallet
+            .authorize_owner_action(&alice, Some(&multisig), Some(&mut proposal.clone()), &withdraw)
+            .is_err());
+        
+        // Approved by the DAO via CPI: executes exactly the approved action, once
+        assert_eq!(proposal.approve(&multisig, &dao).unwrap(), 2);
+        let other = ProposalAction::Withdraw { amount: 1_001 };
+        assert!(wallet
+            .authorize_owner_action(&bob, Some(&multisig), Some(&mut proposal.clone()), &other)
+            .is_err());
+        assert!(wallet
+            .authorize_owner_action(&Pubkey::new_unique(), Some(&multisig), Some(&mut proposal.clone()), &withdraw)
+            .is_err());
+        let mut stale = proposal.clone();
+        wallet.authorize_owner_action(&bob, Some(&multisig), Some(&mut proposal), &withdraw).unwrap();
+        assert!(proposal.executed);
+        assert!(wallet.authorize_owner_action(&bob, Some(&multisig), Some(&mut proposal), &withdraw).is_err());
+        
+        // Changing the owner set invalidates pending proposals
+        multisig.set_owners(vec![alice, bob], 2).unwrap();
+        assert!(wallet.authorize_owner_action(&bob, Some(&multisig), Some(&mut stale), &withdraw).is_err());
+        
+        // Strategy and operator changes queued on a locked wallet map to proposals
+        let operator = Pubkey::new_unique();
+        assert!(ConfigChange::Operator { operator }.proposal_action() == ProposalAction::SetOperator { operator });
+    }
+    
+    #[test]
+    fn test_social_recovery() {
+        use recovery::MIN_RECOVERY_CHALLENGE_SECONDS as CHALLENGE;
+        
+        let guardians = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let new_owner = Pubkey::new_unique();
+        let mut recovery = RecoveryConfig {
+            wallet: Pubkey::default(),
+            bump: 0,
+            guardians: Vec::new(),
+            threshold: 0,
+            challenge_seconds: 0,
+            new_owner: Pubkey::default(),
+            approvals: 0,
+            started_at: 0,
+            executable_at: 0,
+            reserved: [0; 32],
+        };
+        assert!(recovery.set_guardians(guardians.to_vec(), 4, CHALLENGE).is_err());
+        assert!(recovery.set_guardians(guardians.to_vec(), 2, CHALLENGE - 1).is_err());
+        recovery.set_guardians(guardians.to_vec(), 2, CHALLENGE).unwrap();
+        
+        // Only guardians start and back a recovery, one at a time
+        assert!(recovery.start(&new_owner, new_owner, 100).is_err());
+        assert_eq!(recovery.start(&guardians[0], new_owner, 100).unwrap(), 1);
+        assert!(recovery.start(&guardians[1], Pubkey::new_unique(), 100).is_err());
+        assert!(recovery.support(&guardians[0], 100).is_err());
+        
+        // Below threshold the challenge period hasn't started
+        assert!(!recovery.is_ready(100 + CHALLENGE));
+        
+        // The challenge period runs from the threshold being reached
+        assert_eq!(recovery.support(&guardians[2], 1_000).unwrap(), 2);
+        assert_eq!(recovery.executable_at, 1_000 + CHALLENGE);
+        assert!(!recovery.is_ready(1_000 + CHALLENGE - 1));
+        assert!(recovery.is_ready(1_000 + CHALLENGE));
+        
+        // A veto (or new guardian set) drops it
+        let mut vetoed = recovery.clone();
+        vetoed.clear();
+        assert!(!vetoed.is_ready(1_000 + CHALLENGE));
+        assert!(vetoed.support(&guardians[1], 1_000).is_err());
+        recovery.set_guardians(guardians[..2].to_vec(), 1, CHALLENGE).unwrap();
+        assert!(!recovery.is_active());
+    }
+    
+    #[test]
+    fn test_operator_permissions() {
+        let owner = Pubkey::new_unique();
+        let legacy = Pubkey::new_unique();
+        let seller = Pubkey::new_unique();
+        let wallet = MmWallet {
+            owner,
+            operator: legacy,
+            config: StrategyConfig {
+                slippage_bps: 500,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut registry = OperatorRegistry {
+            wallet: Pubkey::default(),
+            bump: 0,
+            operators: Vec::new(),
+            reserved: [0; 64],
+        };
+        let entry = OperatorEntry {
+            key: seller,
+            roles: ROLE_SELL,
+            max_slippage_bps: 100,
+            max_trade_lamports: 1_000,
+        };
+        registry.add(entry).unwrap();
+        assert!(registry.add(entry).is_err());
+        assert!(registry.add(OperatorEntry { key: Pubkey::new_unique(), roles: 0, ..entry }).is_err());
+        
+        // Owner has every role; the legacy operator keeps its trade roles
+        assert_eq!(wallet.permissions(&owner, None).unwrap(), Permissions::OWNER);
+        assert!(wallet.permissions(&legacy, None).unwrap().require(ROLE_BUY | ROLE_SWAP).is_ok());
+        assert!(wallet.permissions(&legacy, None).unwrap().require(ROLE_PAUSE).is_err());
+        
+        // Registered operators only get their roles, and only with the registry
+        assert!(wallet.permissions(&seller, None).is_err());
+        let perms = wallet.permissions(&seller, Some(&registry)).unwrap();
+        assert!(perms.require(TradeSide::Sell.role()).is_ok());
+        assert!(perms.require(TradeSide::Buy.role()).is_err());
+        assert!(perms.require(TradeSide::Sell.role() | Venue::PumpSwap.role()).is_err());
+        
+        // Ceilings: slippage tightened to 1%, SOL leg capped
+        assert_eq!(wallet.calculate_min_output_capped(1_000, perms.max_slippage_bps).unwrap(), 990);
+        assert_eq!(wallet.calculate_min_output_capped(1_000, 0).unwrap(), 950);
+        let fill = |sol| Fill { side: TradeSide::Sell, realized_out: sol, ..Default::default() };
+        assert!(perms.check_fill(&fill(1_000)).is_ok());
+        assert!(perms.check_fill(&fill(1_001)).is_err());
+        
+        registry.remove(&seller).unwrap();
+        assert!(wallet.permissions(&seller, Some(&registry)).is_err());
+        
+        // Once the wallet has a registry, a restricted legacy operator can't drop it
+        // to get its legacy roles back
+        let wallet = MmWallet { operator_registry: true, ..wallet };
+        registry.add(OperatorEntry { key: legacy, ..entry }).unwrap();
+        assert!(wallet.permissions(&legacy, None).is_err());
+        assert!(wallet.permissions(&legacy, Some(&registry)).unwrap().require(ROLE_BUY).is_err());
+        assert_eq!(wallet.permissions(&owner, None).unwrap(), Permissions::OWNER);
+    }
+    
+    #[test]
+    fn test_guardian() {
+        let guardian = Pubkey::new_unique();
+        let mut wallet = MmWallet::default();
+        
+        // No guardian set: the default key must not match
+        assert!(!wallet.is_guardian(&Pubkey::default()));
+        
+        wallet.guardian = guardian;
+        assert!(wallet.is_guardian(&guardian));
+        assert!(!wallet.is_guardian(&wallet.owner));
+        
+        // A guardian holds no operator roles
+        assert!(wallet.permissions(&guardian, None).is_err());
+    }
+    
+    #[test]
+    fn test_protocol_config() {
+        let mut protocol = ProtocolConfig {
+            admin: Pubkey::new_unique(),
+            bump: 255,
+            paused: false,
+            guardian: Pubkey::default(),
+            allowed_programs: vec![],
+            bounds: ConfigBounds::DEFAULT,
+            treasury: Pubkey::default(),
+            fee_bps_by_tier: [0; protocol::FEE_TIERS],
+            reserved: [0u8; 24],
+        };
+        let config = StrategyConfig {
+            trade_size_pct: 25,
+            slippage_bps: 500,
+            ..Default::default()
+        };
+        
+        // Only allowlisted programs
+        assert!(protocol.check_trade(&config, &PUMP_FUN_PROGRAM).is_err());
+        protocol.apply(ProtocolConfigParams {
+            guardian: Pubkey::default(),
+            allowed_programs: vec![PUMP_FUN_PROGRAM, PUMPSWAP_PROGRAM],
+            bounds: ConfigBounds { max_trade_pct: 20, min_slippage_bps: 50, max_slippage_bps: 1000 },
+            treasury: Pubkey::new_unique(),
+            fee_bps_by_tier: [30, 10, 0, 0],
+        });
+        assert!(protocol.check_cpi_allowed(&PUMPSWAP_PROGRAM).is_ok());
+        
+        // Tightened bounds override the built-in ones
+        assert!(protocol.check_trade(&config, &PUMP_FUN_PROGRAM).is_err());
+        let config = StrategyConfig { trade_size_pct: 20, ..config };
+        assert!(protocol.check_trade(&config, &PUMP_FUN_PROGRAM).is_ok());
+        
+        // Global kill switch
+        protocol.paused = true;
+        assert!(protocol.check_cpi_allowed(&PUMP_FUN_PROGRAM).is_err());
+        
+        // Bounds and allowlist validation
+        assert!(ConfigBounds { max_trade_pct: 0, ..ConfigBounds::DEFAULT }.validate().is_err());
+        assert!(ConfigBounds { min_slippage_bps: 6000, ..ConfigBounds::DEFAULT }.validate().is_err());
+        let too_many = ProtocolConfigParams {
+            guardian: Pubkey::default(),
+            allowed_programs: vec![Pubkey::new_unique(); protocol::MAX_ALLOWED_PROGRAMS + 1],
+            bounds: ConfigBounds::DEFAULT,
+            treasury: Pubkey::default(),
+            fee_bps_by_tier: [0; protocol::FEE_TIERS],
+        };
+        assert!(too_many.validate().is_err());
+        assert!(!protocol.is_guardian(&Pubkey::default()));
+    }
+    
+    #[test]
+    fn test_fee_split() {
+        let (dev, marketer, treasury) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let share = |key, bps| FeeShare { key, bps };
+        let mut split = FeeSplit {
+            wallet: Pubkey::new_unique(),
+            bump: 255,
+            recipients: vec![],
+            distributed: 0,
+            reserved: [0; 32],
+        };
+        
+        // Weights must be unique, non-zero and sum to 10000 bps
+        assert!(split.set_recipients(&[share(dev, 5000), share(marketer, 4000)]).is_err());
+        assert!(split.set_recipients(&[share(dev, 5000), share(dev, 5000)]).is_err());
+        assert!(split.set_recipients(&[share(dev, 10_000), share(marketer, 0)]).is_err());
+        assert!(split.set_recipients(&[]).is_err());
+        split.set_recipients(&[share(dev, 5000), share(marketer, 3000), share(treasury, 2000)]).unwrap();
+        
+        // 1_000_001 claimed: shares round down, the dust stays pending
+        let payouts = split.shares(split.undistributed(1_000_001));
+        assert_eq!(payouts, vec![500_000, 300_000, 200_000]);
+        assert_eq!(split.record_payout(&payouts), 1_000_000);
+        assert_eq!(split.undistributed(1_000_001), 1);
+        
+        // Replacing the table keeps the history of recipients that stay
+        split.set_recipients(&[share(dev, 6000), share(treasury, 4000)]).unwrap();
+        assert_eq!(split.recipients[0].paid, 500_000);
+        assert_eq!(split.recipients[1].paid, 200_000);
+        assert_eq!(split.distributed, 1_000_000);
+    }
     
-    /// Validated by Anchor's Mint type
-    pub token_mint_account: Account<'info, Mint>,
+    #[test]
+    fn test_fee_split_with_fee_history() {
+        let (dev, marketer) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let share = |key, bps| FeeShare { key, bps };
+        let mut split = FeeSplit {
+            wallet: Pubkey::new_unique(),
+            bump: 255,
+            recipients: vec![],
+            distributed: 0,
+            reserved: [0; 32],
+        };
+        
+        // 3 SOL claimed (and mostly withdrawn) before the split existed: none of it is owed
+        let mut wallet = MmWallet {
+            total_fees_claimed: 3_000_000_000,
+            principal: 10_000_000_000,
+            ..Default::default()
+        };
+        split.init_table(&[share(dev, 5000), share(marketer, 5000)], wallet.total_fees_claimed).unwrap();
+        assert_eq!(split.undistributed(wallet.total_fees_claimed), 0);
+        
+        // A later claim is owed, but payouts never dip into principal
+        wallet.total_fees_claimed += 500_000_000;
+        let balance = MIN_RENT_RESERVE + wallet.principal + 200_000_000;
+        let amount = split.undistributed(wallet.total_fees_claimed).min(wallet.withdrawable_profit(balance));
+        assert_eq!(amount, 200_000_000);
+        let payouts = split.shares(amount);
+        split.record_payout(&payouts);
+        assert_eq!(split.undistributed(wallet.total_fees_claimed), 300_000_000);
+        
+        // Re-submitting the same table keeps what is owed...
+        split.set_table(&[share(dev, 5000), share(marketer, 5000)], wallet.total_fees_claimed).unwrap();
+        assert_eq!(split.undistributed(wallet.total_fees_claimed), 300_000_000);
+        
+        // ... and a changed table has to wait until the old one is paid
+        assert!(split.set_table(&[share(dev, 10_000)], wallet.total_fees_claimed).is_err());
+        assert_eq!(split.recipients.len(), 2);
+        let payouts = split.shares(split.undistributed(wallet.total_fees_claimed));
+        assert_eq!(payouts, vec![150_000_000, 150_000_000]);
+        split.record_payout(&payouts);
+        split.set_table(&[share(dev, 10_000)], wallet.total_fees_claimed).unwrap();
+        assert_eq!(split.recipients[0].paid, 250_000_000);
+        
+        // Rounding dust doesn't block a change; it carries over to the new table
+        split.set_table(&[share(dev, 5000), share(marketer, 5000)], wallet.total_fees_claimed).unwrap();
+        wallet.total_fees_claimed += 1;
+        split.set_table(&[share(marketer, 10_000)], wallet.total_fees_claimed).unwrap();
+        assert_eq!(split.undistributed(wallet.total_fees_claimed), 1);
+    }
     
-    pub owner: Signer<'info>,
-}
-
-#[derive(Accounts)]
-pub struct UpdateConfig<'info> {
-    #[account(mut)]
-    pub mm_wallet: Account<'info, MmWallet>,
+    #[test]
+    fn test_profit_withdrawals_leave_split_fees() {
+        let sol = 1_000_000_000u64;
+        let mut split = FeeSplit {
+            wallet: Pubkey::new_unique(),
+            bump: 255,
+            recipients: vec![],
+            distributed: 0,
+            reserved: [0; 32],
+        };
+        split.init_table(&[FeeShare { key: Pubkey::new_unique(), bps: 10_000 }], 0).unwrap();
+        let mut wallet = MmWallet { principal: 10 * sol, fee_split: true, ..Default::default() };
+        let mut balance = MIN_RENT_RESERVE + 10 * sol;
+        
+        // Claim 1 SOL of fees and make 0.5 SOL of trading profit
+        balance += sol;
+        wallet.total_fees_claimed += sol;
+        balance += sol / 2;
+        
+        // Only the trading profit can be withdrawn; the fees are owed to the split
+        assert_eq!(wallet.withdrawable_profit_after_fees(balance, Some(&split)), sol / 2);
+        balance -= sol / 2;
+        assert_eq!(wallet.withdrawable_profit_after_fees(balance, Some(&split)), 0);
+        
+        // Distribution still finds the full 1 SOL
+        let amount = split.undistributed(wallet.total_fees_claimed).min(wallet.withdrawable_profit(balance));
+        assert_eq!(amount, sol);
+        split.record_payout(&split.shares(amount));
+        balance -= amount;
+        assert_eq!(balance, MIN_RENT_RESERVE + wallet.principal);
+        
+        // A second claim, partly distributed before the next withdrawal
+        balance += sol;
+        wallet.total_fees_claimed += sol;
+        let amount = sol / 4;
+        split.record_payout(&split.shares(amount));
+        balance -= amount;
+        assert_eq!(wallet.withdrawable_profit_after_fees(balance, Some(&split)), 0);
+        assert_eq!(wallet.withdrawable_profit(balance), 3 * sol / 4);
+    }
     
-    pub owner: Signer<'info>,
-}
-
-// ═══════════════════════════════════════════════════════════════════════════════
-// TESTS (Unit tests for security-critical functions)
-// ═══════════════════════════════════════════════════════════════════════════════
+    #[test]
+    fn test_protocol_fee_tiers() {
+        let params = ProtocolConfigParams {
+            guardian: Pubkey::default(),
+            allowed_programs: vec![PUMP_FUN_PROGRAM],
+            bounds: ConfigBounds::DEFAULT,
+            treasury: Pubkey::new_unique(),
+            fee_bps_by_tier: [30, 10, 0, 0],
+        };
+        assert!(params.validate().is_ok());
+        
+        let mut protocol = ProtocolConfig {
+            admin: Pubkey::new_unique(),
+            bump: 255,
+            paused: false,
+            guardian: Pubkey::default(),
+            allowed_programs: vec![],
+            bounds: ConfigBounds::DEFAULT,
+            treasury: Pubkey::default(),
+            fee_bps_by_tier: [0; protocol::FEE_TIERS],
+            reserved: [0u8; 24],
+        };
+        protocol.apply(params.clone());
+        
+        // Standard, discounted and exempt tiers
+        assert_eq!(protocol.protocol_fee(0, 1_000_000_000).unwrap(), 3_000_000);
+        assert_eq!(protocol.protocol_fee(1, 1_000_000_000).unwrap(), 1_000_000);
+        assert_eq!(protocol.protocol_fee(2, 1_000_000_000).unwrap(), 0);
+        assert!(protocol.protocol_fee(protocol::FEE_TIERS as u8, 1_000_000_000).is_err());
+        
+        // Fee ceiling, and a treasury is required once any tier charges
+        let too_high = ProtocolConfigParams { fee_bps_by_tier: [101, 0, 0, 0], ..params.clone() };
+        assert!(too_high.validate().is_err());
+        let no_treasury = ProtocolConfigParams { treasury: Pubkey::default(), ..params };
+        assert!(no_treasury.validate().is_err());
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_session_key_lifecycle() {
+        let clock = Clock { slot: 100, unix_timestamp: 1_000, ..Default::default() };
+        
+        // Expiry must be set and in the future; pause can't be delegated
+        assert!(SessionKey::validate_params(ROLE_BUY, 0, 0, 1, &clock).is_err());
+        assert!(SessionKey::validate_params(ROLE_BUY, 1_000, 0, 1, &clock).is_err());
+        assert!(SessionKey::validate_params(ROLE_PAUSE, 2_000, 0, 1, &clock).is_err());
+        assert!(SessionKey::validate_params(ROLE_BUY, 0, 0, 0, &clock).is_err());
+        assert!(SessionKey::validate_params(ROLE_BUY | ROLE_SELL, 2_000, 200, 1_000, &clock).is_ok());
+        
+        let mut session = SessionKey {
+            wallet: Pubkey::default(),
+            key: Pubkey::new_unique(),
+            bump: 0,
+            roles: ROLE_BUY,
+            expires_at: 2_000,
+            expires_at_slot: 200,
+            allowance_lamports: 1_000,
+            spent_lamports: 0,
+            revoked: false,
+            created_at: 1_000,
+            reserved: [0; 32],
+        };
+        
+        let perms = session.permissions(&clock).unwrap();
+        assert!(perms.require(ROLE_BUY).is_ok());
+        assert!(perms.require(ROLE_SELL).is_err());
+        
+        // Either expiry ends the session
+        assert!(session.permissions(&Clock { slot: 200, ..clock.clone() }).is_err());
+        assert!(session.permissions(&Clock { unix_timestamp: 2_000, ..clock.clone() }).is_err());
+        
+        // Allowance is spent down, then the session revokes itself
+        assert!(session.consume(1_001).is_err());
+        assert!(!session.consume(600).unwrap());
+        assert!(session.consume(400).unwrap());
+        assert_eq!((session.allowance_lamports, session.spent_lamports), (0, 1_000));
+        assert!(session.permissions(&clock).is_err());
+    }
     
     #[test]
-    fn test_calculate_min_output() {
+    fn test_migrate_legacy_wallet() {
+        let owner = Pubkey::new_unique();
         let wallet = MmWallet {
-            config: StrategyConfig {
-                slippage_bps: 1000, // 10%
-                ..Default::default()
-            },
+            version: 2,
+            owner,
+            nonce: 7,
+            total_trades: 42,
             ..Default::default()
         };
         
-        // 100 tokens with 10% slippage = 90 min
-        let result = wallet.calculate_min_output(100).unwrap();
-        assert_eq!(result, 90);
+        // A v2 account: fields through `realized_pnl`, then 8 zero bytes (+ config padding)
+        let mut data = Vec::new();
+        wallet.try_serialize(&mut data).unwrap();
+        let fields_len = data.len() - (MmWallet::SIZE - MmWallet::V2_SIZE) - 8;
+        data.truncate(fields_len);
+        data.resize(MmWallet::V2_SIZE, 0);
+        assert!(MmWallet::try_deserialize(&mut &data[..]).is_err());
+        
+        // Realloc zero-extends, then the fields are filled in
+        data.resize(MmWallet::SIZE, 0);
+        MmWallet::migrate_data(&mut data, 5_000_000_000).unwrap();
+        
+        let migrated = MmWallet::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!(migrated.version, PROGRAM_VERSION);
+        assert_eq!(migrated.owner, owner);
+        assert_eq!(migrated.principal, 5_000_000_000 - MIN_RENT_RESERVE);
+        assert_eq!(migrated.seed_owner, owner);
+        assert_eq!(migrated.pending_owner, Pubkey::default());
+        assert_eq!((migrated.nonce, migrated.total_trades), (7, 42));
+        assert!(!migrated.vesting.is_set());
     }
     
     #[test]
-    fn test_max_trade_amount() {
-        let wallet = MmWallet {
-            config: StrategyConfig {
-                trade_size_pct: 25, // 25%
-                ..Default::default()
-            },
+    fn test_withdrawable_profit() {
+        let mut wallet = MmWallet {
+            principal: 10_000_000_000,
             ..Default::default()
         };
         
-        // 1000 available, 25% max = 250
-        let result = wallet.max_trade_amount(1000).unwrap();
-        assert_eq!(result, 250);
+        // Claimed fees and realized profit sit above the principal
+        let balance = 10_000_000_000 + MIN_RENT_RESERVE + 750_000_000;
+        assert_eq!(wallet.withdrawable_profit(balance), 750_000_000);
+        
+        // Principal tied up in tokens leaves no profit to take
+        assert_eq!(wallet.withdrawable_profit(6_000_000_000), 0);
+        
+        // No principal: everything above the rent reserve
+        wallet.principal = 0;
+        assert_eq!(wallet.withdrawable_profit(balance), balance - MIN_RENT_RESERVE);
+        assert_eq!(wallet.withdrawable_profit(MIN_RENT_RESERVE / 2), 0);
     }
     
     #[test]
-    fn test_is_locked() {
+    fn test_token_lock() {
+        // Team supply locked on a 90-day cliff, independent of the SOL lock
+        let cliff = VestingParams {
+            cliff_seconds: 90 * 86_400,
+            duration_seconds: 90 * 86_400,
+            locked_principal: 1_000_000,
+        };
         let wallet = MmWallet {
-            lock_until: 1000,
+            token_vesting: cliff.schedule(0).unwrap(),
             ..Default::default()
         };
         
-        assert!(wallet.is_locked(500)); // Before lock expires
-        assert!(!wallet.is_locked(1000)); // At expiration
-        assert!(!wallet.is_locked(1500)); // After expiration
+        // Only tokens above the locked amount can leave before the cliff
+        assert_eq!(wallet.withdrawable_tokens(1_500_000, 86_400), 500_000);
+        assert_eq!(wallet.withdrawable_tokens(800_000, 86_400), 0);
+        assert_eq!(wallet.withdrawable_tokens(1_500_000, 90 * 86_400), 1_500_000);
+        
+        // Linear token vesting
+        let linear = VestingParams { cliff_seconds: 0, ..cliff };
+        let wallet = MmWallet {
+            token_vesting: linear.schedule(0).unwrap(),
+            ..Default::default()
+        };
+        assert_eq!(wallet.withdrawable_tokens(1_000_000, 45 * 86_400), 500_000);
+        
+        // Sells can't cut into the locked amount either
+        assert!(wallet.check_token_lock(500_000, 45 * 86_400).is_ok());
+        assert!(wallet.check_token_lock(499_999, 45 * 86_400).is_err());
+        assert!(wallet.check_token_lock(0, 90 * 86_400).is_ok());
+        
+        // No token lock
+        assert_eq!(MmWallet::default().withdrawable_tokens(1_000_000, 0), 1_000_000);
+        assert!(MmWallet::default().check_token_lock(0, 0).is_ok());
     }
     
     #[test]
-    fn test_can_trade_rate_limiting() {
+    fn test_vesting_schedule() {
+        let params = VestingParams {
+            cliff_seconds: 100,
+            duration_seconds: 1000,
+            locked_principal: 1_000_000_000,
+        };
+        let vesting = params.schedule(10_000).unwrap();
+        assert_eq!((vesting.cliff, vesting.end), (10_100, 11_000));
+        
+        // Nothing before the cliff, linear after it, everything at the end
+        assert_eq!(vesting.vested(10_099), 0);
+        assert_eq!(vesting.vested(10_100), 100_000_000);
+        assert_eq!(vesting.vested(10_500), 500_000_000);
+        assert_eq!(vesting.locked(10_500), 500_000_000);
+        assert_eq!(vesting.vested(11_000), 1_000_000_000);
+        
+        // Withdrawals leave the rent reserve and the unvested principal
+        let wallet = MmWallet { vesting, ..Default::default() };
+        let balance = 2_000_000_000;
+        assert_eq!(wallet.withdrawable(balance, 10_500), balance - MIN_RENT_RESERVE - 500_000_000);
+        assert_eq!(wallet.withdrawable(balance, 11_000), balance - MIN_RENT_RESERVE);
+        assert_eq!(MmWallet::default().withdrawable(balance, 0), balance - MIN_RENT_RESERVE);
+        
+        // Extending pushes the end back, never unlocking more
+        let mut extended = vesting;
+        extended.extend(1000).unwrap();
+        assert!(extended.vested(10_500) < vesting.vested(10_500));
+        
+        // Invalid schedules
+        assert!(VestingParams { cliff_seconds: 1001, ..params }.validate().is_err());
+        assert!(VestingParams { duration_seconds: 0, cliff_seconds: 0, ..params }.validate().is_err());
+        assert!(VestingParams { locked_principal: 0, ..params }.validate().is_err());
+    }
+    
+    #[test]
+    fn test_drawdown_circuit_breaker() {
+        let mut limits = risk_limits(RiskLimitsConfig {
+            max_drawdown_bps: 2_000, // 20%
+            ..Default::default()
+        });
+        
+        // 1 SOL + 1_000 tokens at 0.001 SOL each = 2 SOL equity
+        let holdings = BalanceSnapshot { lamports: 1_000_000_000, tokens: 1_000 };
+        let price = 1_000_000 * PRICE_SCALE as u64;
+        assert_eq!(holdings.equity(price), 2_000_000_000);
+        
+        assert_eq!(limits.check_drawdown(holdings.equity(price)), None);
+        assert_eq!(limits.high_water_mark, 2_000_000_000);
+        
+        // Price halves: equity 1.5 SOL, 25% drawdown
+        assert_eq!(limits.check_drawdown(holdings.equity(price / 2)), Some(2_500));
+        assert_eq!(limits.high_water_mark, 2_000_000_000);
+        
+        // Exactly at threshold does not trip
+        assert_eq!(limits.check_drawdown(1_600_000_000), None);
+        
+        // Disabled threshold never trips
+        limits.limits.max_drawdown_bps = 0;
+        assert_eq!(limits.check_drawdown(1), None);
+        
+        assert!(RiskLimitsConfig { max_drawdown_bps: 10_001, ..Default::default() }.validate().is_err());
+    }
+    
+    fn remaining_accounts<'a>(keys: &'a [Pubkey], lamports: &'a mut [u64]) -> Vec<AccountInfo<'a>> {
+        keys.iter()
+            .zip(lamports.iter_mut())
+            .map(|(key, lamports)| AccountInfo::new(key, false, false, lamports, &mut [], key, false, 0))
+            .collect()
+    }
+    
+    #[test]
+    fn test_validate_pump_fun_accounts() {
+        let mint = Pubkey::new_unique();
+        let pda_wallet = Pubkey::new_unique();
+        let curve = pump_fun::bonding_curve_address(&mint);
+        let pda_ata = anchor_spl::associated_token::get_associated_token_address(&pda_wallet, &mint);
+        
+        let mut keys: Vec<Pubkey> = (0..pump_fun::SELL_ACCOUNTS_LEN).map(|_| Pubkey::new_unique()).collect();
+        keys[pump_fun::account_index::GLOBAL] = pump_fun::PUMP_GLOBAL;
+        keys[pump_fun::account_index::MINT] = mint;
+        keys[pump_fun::account_index::BONDING_CURVE] = curve;
+        keys[pump_fun::account_index::ASSOCIATED_BONDING_CURVE] =
+            anchor_spl::associated_token::get_associated_token_address(&curve, &mint);
+        keys[pump_fun::account_index::ASSOCIATED_USER] = pda_ata;
+        keys[pump_fun::account_index::USER] = pda_wallet;
+        keys[pump_fun::account_index::PROGRAM] = PUMP_FUN_PROGRAM;
+        
+        let mut lamports = vec![0u64; keys.len()];
+        let accounts = remaining_accounts(&keys, &mut lamports);
+        let validate = |accounts: &[AccountInfo], mint: &Pubkey| {
+            pump_fun::validate_trade_accounts(
+                accounts,
+                mint,
+                &pda_wallet,
+                &pump_fun::bonding_curve_address(mint),
+                &anchor_spl::associated_token::get_associated_token_address(&pda_wallet, mint),
+                pump_fun::SELL_ACCOUNTS_LEN,
+            )
+        };
+        
+        assert!(validate(&accounts, &mint).is_ok());
+        
+        // Same accounts for a different configured mint
+        assert_eq!(
+            validate(&accounts, &Pubkey::new_unique()).unwrap_err(),
+            MmWalletError::TokenMintMismatch.into()
+        );
+        
+        // Truncated account list
+        assert_eq!(
+            validate(&accounts[..pump_fun::SELL_ACCOUNTS_LEN - 1], &mint).unwrap_err(),
+            MmWalletError::InvalidRemainingAccounts.into()
+        );
+    }
+    
+    #[test]
+    fn test_validate_creator_fee_accounts() {
+        use pumpswap::creator_fee_index as idx;
+        
+        let pda_wallet = Pubkey::new_unique();
+        let pda_quote_ata = get_associated_token_address(&pda_wallet, &native_mint::ID);
+        let vault_authority = pumpswap::coin_creator_vault_authority(&pda_wallet);
+        
+        let mut keys: Vec<Pubkey> = (0..pumpswap::CREATOR_FEE_ACCOUNTS_LEN).map(|_| Pubkey::new_unique()).collect();
+        keys[idx::QUOTE_MINT] = native_mint::ID;
+        keys[idx::QUOTE_TOKEN_PROGRAM] = token::ID;
+        keys[idx::COIN_CREATOR] = pda_wallet;
+        keys[idx::COIN_CREATOR_VAULT_AUTHORITY] = vault_authority;
+        keys[idx::COIN_CREATOR_VAULT_ATA] = get_associated_token_address(&vault_authority, &native_mint::ID);
+        keys[idx::COIN_CREATOR_TOKEN_ACCOUNT] = pda_quote_ata;
+        keys[idx::PROGRAM] = PUMPSWAP_PROGRAM;
+        
+        let mut lamports = vec![0u64; keys.len()];
+        let accounts = remaining_accounts(&keys, &mut lamports);
+        assert!(pumpswap::validate_creator_fee_accounts(&accounts, &pda_wallet, &pda_quote_ata).is_ok());
+        
+        // Fees routed to a token account the PDA does not own
+        let other = Pubkey::new_unique();
+        assert!(pumpswap::validate_creator_fee_accounts(&accounts, &pda_wallet, &other).is_err());
+        
+        // Another creator's vault
+        assert!(pumpswap::validate_creator_fee_accounts(&accounts, &other, &pda_quote_ata).is_err());
+        
+        // Per-venue stats
         let wallet = MmWallet {
-            last_trade: 1000,
-            config: StrategyConfig {
-                min_delay_secs: 60,
-                ..Default::default()
-            },
+            total_fees_claimed: 1_500,
+            swap_fees_claimed: 400,
             ..Default::default()
         };
+        assert_eq!(wallet.curve_fees_claimed(), 1_100);
+    }
+    
+    #[test]
+    fn test_realized_deltas() {
+        let before = BalanceSnapshot { lamports: 1_000, tokens: 50 };
         
-        assert!(!wallet.can_trade(1030)); // 30s later - too soon
-        assert!(!wallet.can_trade(1059)); // 59s later - still too soon
-        assert!(wallet.can_trade(1060)); // 60s later - OK
-        assert!(wallet.can_trade(1061)); // 61s later - OK
+        // Buy: spent 300 lamports, received 20 tokens
+        let after_buy = BalanceSnapshot { lamports: 700, tokens: 70 };
+        assert_eq!(before.realized(&after_buy, true), (300, 20));
+        
+        // Sell: spent 50 tokens, received 400 lamports
+        let after_sell = BalanceSnapshot { lamports: 1_400, tokens: 0 };
+        assert_eq!(before.realized(&after_sell, false), (50, 400));
+    }
+    
+    #[test]
+    fn test_verify_realized() {
+        assert!(verify_realized(100, 90, 100, 90).is_ok());
+        assert!(verify_realized(101, 90, 100, 90).is_err()); // overspent
+        assert!(verify_realized(100, 89, 100, 90).is_err()); // below floor
+        assert!(verify_realized(100, 0, 100, 0).is_err()); // nothing received
     }
     
     #[test]
@@ -1494,7 +6490,7 @@ mod tests {
             max_delay_secs: 60,
             ..Default::default()
         };
-        assert!(validate_config(&valid).is_ok());
+        assert!(validate_config(&valid, &ConfigBounds::DEFAULT).is_ok());
         
         // Invalid trade size (too high)
         let invalid_size = StrategyConfig {
@@ -1504,7 +6500,7 @@ mod tests {
             max_delay_secs: 60,
             ..Default::default()
         };
-        assert!(validate_config(&invalid_size).is_err());
+        assert!(validate_config(&invalid_size, &ConfigBounds::DEFAULT).is_err());
         
         // Invalid slippage (too low)
         let invalid_slip = StrategyConfig {
@@ -1514,7 +6510,7 @@ mod tests {
             max_delay_secs: 60,
             ..Default::default()
         };
-        assert!(validate_config(&invalid_slip).is_err());
+        assert!(validate_config(&invalid_slip, &ConfigBounds::DEFAULT).is_err());
         
         // Invalid delay (min > max)
         let invalid_delay = StrategyConfig {
@@ -1524,7 +6520,672 @@ mod tests {
             max_delay_secs: 60,
             ..Default::default()
         };
-        assert!(validate_config(&invalid_delay).is_err());
+        assert!(validate_config(&invalid_delay, &ConfigBounds::DEFAULT).is_err());
+    }
+    
+    // ═══ INSTRUCTION HARNESS ═══
+    // Runs instructions through the program entrypoint against in-memory accounts
+    
+    use anchor_lang::solana_program::program_error::ProgramError;
+    use anchor_lang::solana_program::program_stubs::{self, SyscallStubs};
+    use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+    use std::cell::Cell;
+    use std::collections::HashMap;
+    
+    thread_local! {
+        /// Unix time the stubbed clock reports on this test's thread
+        static NOW: Cell<i64> = const { Cell::new(0) };
+    }
+    
+    /// Off-chain syscalls: the clock reads `NOW`, rent is the default schedule and
+    /// everything else (CPIs included) is the default no-op stub
+    struct TestSyscalls;
+    
+    impl SyscallStubs for TestSyscalls {
+        fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+            let clock = Clock { unix_timestamp: NOW.with(Cell::get), ..Default::default() };
+            unsafe { std::ptr::write_unaligned(var_addr as *mut Clock, clock) };
+            0
+        }
+        
+        fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe { std::ptr::write_unaligned(var_addr as *mut Rent, Rent::default()) };
+            0
+        }
+    }
+    
+    /// Account data laid out like the runtime's input buffer (length prefix, then data)
+    /// so `close`, which reallocs in place, stays in bounds
+    #[derive(Clone)]
+    struct TestAccount {
+        lamports: u64,
+        buffer: Vec<u8>,
+        owner: Pubkey,
+        executable: bool,
+    }
+    
+    impl TestAccount {
+        fn data(&self) -> &[u8] {
+            let len = u64::from_le_bytes(self.buffer[..8].try_into().unwrap()) as usize;
+            &self.buffer[8..8 + len]
+        }
+    }
+    
+    struct TestLedger {
+        accounts: HashMap<Pubkey, TestAccount>,
+    }
+    
+    impl TestLedger {
+        fn new(now: i64) -> Self {
+            program_stubs::set_syscall_stubs(Box::new(TestSyscalls));
+            NOW.with(|n| n.set(now));
+            
+            let mut ledger = Self { accounts: HashMap::new() };
+            ledger.insert(crate::ID, 1, Vec::new(), Pubkey::default(), true);
+            ledger.insert(system_program::ID, 1, Vec::new(), Pubkey::default(), true);
+            ledger
+        }
+        
+        fn insert(&mut self, key: Pubkey, lamports: u64, data: Vec<u8>, owner: Pubkey, executable: bool) {
+            let mut buffer = (data.len() as u64).to_le_bytes().to_vec();
+            buffer.extend(data);
+            self.accounts.insert(key, TestAccount { lamports, buffer, owner, executable });
+        }
+        
+        /// A funded system account to sign with
+        fn signer(&mut self) -> Pubkey {
+            let key = Pubkey::new_unique();
+            self.insert(key, 1_000_000_000, Vec::new(), system_program::ID, false);
+            key
+        }
+        
+        /// Store a program account at `key`, zero-padded to `space`
+        fn put<T: AccountSerialize>(&mut self, key: Pubkey, account: &T, space: usize) {
+            let mut data = Vec::with_capacity(space);
+            account.try_serialize(&mut data).unwrap();
+            data.resize(space, 0);
+            self.insert(key, 10_000_000, data, crate::ID, false);
+        }
+        
+        /// An empty program account at `key` for an `init` constraint to fill in
+        /// (the system program CPIs that would create it are stubbed out)
+        fn reserve(&mut self, key: Pubkey, space: usize) {
+            self.insert(key, 10_000_000, vec![0; space], crate::ID, false);
+        }
+        
+        fn get<T: AccountDeserialize>(&self, key: &Pubkey) -> T {
+            T::try_deserialize(&mut self.accounts[key].data()).unwrap()
+        }
+        
+        /// Whether `key` is still a live program account (not closed)
+        fn is_open(&self, key: &Pubkey) -> bool {
+            self.accounts[key].owner == crate::ID
+        }
+        
+        /// Run `ix`; a failed instruction leaves the ledger untouched, like a reverted transaction
+        fn process(&mut self, accounts: impl ToAccountMetas, ix: impl InstructionData) -> std::result::Result<(), ProgramError> {
+            let snapshot = self.accounts.clone();
+            let result = self.execute(accounts, ix);
+            if result.is_err() {
+                self.accounts = snapshot;
+            }
+            
+            result
+        }
+        
+        fn execute(&mut self, accounts: impl ToAccountMetas, ix: impl InstructionData) -> std::result::Result<(), ProgramError> {
+            let metas = accounts.to_account_metas(None);
+            let infos: HashMap<Pubkey, AccountInfo> = self
+                .accounts
+                .iter_mut()
+                .map(|(key, account)| {
+                    let TestAccount { lamports, buffer, owner, executable } = account;
+                    let info = AccountInfo::new(key, false, false, lamports, &mut buffer[8..], owner, *executable, 0);
+                    (*key, info)
+                })
+                .collect();
+            let account_infos: Vec<AccountInfo> = metas
+                .iter()
+                .map(|meta| {
+                    let mut info = infos[&meta.pubkey].clone();
+                    info.is_signer = meta.is_signer;
+                    info.is_writable = meta.is_writable;
+                    info
+                })
+                .collect();
+            
+            entry(&crate::ID, &account_infos, &ix.data())
+        }
+    }
+    
+    fn custom(error: MmWalletError) -> std::result::Result<(), ProgramError> {
+        Err(ProgramError::Custom(error.into()))
+    }
+    
+    fn pda(seeds: &[&[u8]]) -> (Pubkey, u8) {
+        Pubkey::find_program_address(seeds, &crate::ID)
+    }
+    
+    /// A 2-of-3 multisig wallet, with `owners[0]` as its owner key
+    fn multisig_wallet(now: i64) -> (TestLedger, Pubkey, Pubkey, [Pubkey; 3]) {
+        let mut ledger = TestLedger::new(now);
+        let owners = [ledger.signer(), ledger.signer(), ledger.signer()];
+        let wallet = Pubkey::new_unique();
+        ledger.put(wallet, &MmWallet { owner: owners[0], multisig: true, ..Default::default() }, MmWallet::SIZE);
+        
+        let (multisig, bump) = pda(&[b"multisig", wallet.as_ref()]);
+        let owner_set = OwnerMultisig {
+            wallet,
+            bump,
+            owners: owners.to_vec(),
+            threshold: 2,
+            generation: 0,
+            proposal_count: 0,
+            reserved: [0; 32],
+        };
+        ledger.put(multisig, &owner_set, OwnerMultisig::SIZE);
+        
+        (ledger, wallet, multisig, owners)
+    }
+    
+    /// Store proposal `index` for `action`, approved by `owners[0]` (the proposer)
+    fn put_proposal(ledger: &mut TestLedger, wallet: Pubkey, index: u64, action: ProposalAction) -> Pubkey {
+        let (key, bump) = pda(&[b"proposal", wallet.as_ref(), &index.to_le_bytes()]);
+        let proposal = Proposal {
+            wallet,
+            index,
+            bump,
+            proposer: Pubkey::default(),
+            action,
+            generation: 0,
+            approvals: 0b1,
+            executed: false,
+            created_at: 0,
+            reserved: [0; 16],
+        };
+        ledger.put(key, &proposal, Proposal::SIZE);
+        
+        key
+    }
+    
+    #[test]
+    fn test_multisig_gates_owner_instructions() {
+        let (mut ledger, wallet, multisig, [owner, alice, _]) = multisig_wallet(1_000);
+        let gated = Err(ProgramError::Custom(MmWalletError::MultisigRequired.into()));
+        let owner_action = || accounts::OwnerAction { mm_wallet: wallet, owner, multisig: Some(multisig), proposal: None };
+        
+        // The owner key alone can't transfer ownership, change the guardian or resume
+        assert_eq!(ledger.process(owner_action(), instruction::ProposeOwner { new_owner: alice }), gated);
+        assert_eq!(ledger.process(owner_action(), instruction::SetGuardian { new_guardian: alice }), gated);
+        assert_eq!(ledger.process(owner_action(), instruction::Resume), gated);
+        
+        // ... nor change operators
+        let (operator_registry, bump) = pda(&[b"operators", wallet.as_ref()]);
+        let registry = OperatorRegistry { wallet, bump, operators: Vec::new(), reserved: [0; 64] };
+        ledger.put(operator_registry, &registry, OperatorRegistry::SIZE);
+        let entry = OperatorEntry { key: alice, roles: ROLE_BUY, ..Default::default() };
+        let registry_accounts = || accounts::UpdateOperatorRegistry {
+            mm_wallet: wallet,
+            operator_registry,
+            owner,
+            multisig: Some(multisig),
+            proposal: None,
+        };
+        assert_eq!(ledger.process(registry_accounts(), instruction::AddOperator { entry }), gated);
+        assert_eq!(ledger.process(registry_accounts(), instruction::UpdateOperator { entry }), gated);
+        
+        // ... nor the fee split
+        let (fee_split, bump) = pda(&[b"fee_split", wallet.as_ref()]);
+        let split = FeeSplit { wallet, bump, recipients: Vec::new(), distributed: 0, reserved: [0; 32] };
+        ledger.put(fee_split, &split, FeeSplit::SIZE);
+        let shares = vec![FeeShare { key: alice, bps: 10_000 }];
+        let split_accounts = accounts::UpdateFeeSplit { mm_wallet: wallet, fee_split, owner, multisig: Some(multisig), proposal: None };
+        assert_eq!(ledger.process(split_accounts, instruction::UpdateFeeSplit { shares }), gated);
+        
+        // ... nor recovery guardians
+        let (recovery, bump) = pda(&[b"recovery", wallet.as_ref()]);
+        let config = RecoveryConfig {
+            wallet,
+            bump,
+            guardians: vec![alice],
+            threshold: 1,
+            challenge_seconds: recovery::MIN_RECOVERY_CHALLENGE_SECONDS,
+            new_owner: Pubkey::default(),
+            approvals: 0,
+            started_at: 0,
+            executable_at: 0,
+            reserved: [0; 32],
+        };
+        ledger.put(recovery, &config, RecoveryConfig::SIZE);
+        let recovery_accounts = accounts::UpdateRecovery { mm_wallet: wallet, recovery, owner, multisig: Some(multisig), proposal: None };
+        let update = instruction::UpdateRecovery {
+            guardians: vec![owner],
+            threshold: 1,
+            challenge_seconds: recovery::MIN_RECOVERY_CHALLENGE_SECONDS,
+        };
+        assert_eq!(ledger.process(recovery_accounts, update), gated);
+        
+        // ... nor risk limits and the circuit breaker
+        let (risk_key, bump) = pda(&[b"risk_limits", wallet.as_ref()]);
+        let limits = RiskLimits { wallet, bump, ..risk_limits(RiskLimitsConfig::default()) };
+        ledger.put(risk_key, &limits, RiskLimits::SIZE);
+        let risk_accounts = || accounts::UpdateRiskLimits {
+            mm_wallet: wallet,
+            risk_limits: risk_key,
+            owner,
+            multisig: Some(multisig),
+            proposal: None,
+        };
+        let update = instruction::UpdateRiskLimits { limits: RiskLimitsConfig::default() };
+        assert_eq!(ledger.process(risk_accounts(), update), gated);
+        assert_eq!(ledger.process(risk_accounts(), instruction::ResetCircuitBreaker), gated);
+        
+        // Nothing changed
+        let state: MmWallet = ledger.get(&wallet);
+        assert_eq!((state.pending_owner, state.guardian), (Pubkey::default(), Pubkey::default()));
+        assert!(ledger.get::<OperatorRegistry>(&operator_registry).operators.is_empty());
+        assert_eq!(ledger.get::<RecoveryConfig>(&recovery).guardians, vec![alice]);
+    }
+    
+    #[test]
+    fn test_multisig_proposal_executes_owner_instruction() {
+        let (mut ledger, wallet, multisig, [owner, alice, bob]) = multisig_wallet(1_000);
+        let nominee = ledger.signer();
+        let transfer = ProposalAction::TransferOwnership { new_owner: nominee };
+        let proposal = put_proposal(&mut ledger, wallet, 0, transfer);
+        let owner_action = |signer, proposal| accounts::OwnerAction {
+            mm_wallet: wallet,
+            owner: signer,
+            multisig: Some(multisig),
+            proposal: Some(proposal),
+        };
+        
+        // One approval out of two
+        let propose = || instruction::ProposeOwner { new_owner: nominee };
+        assert_eq!(ledger.process(owner_action(owner, proposal), propose()), custom(MmWalletError::ProposalNotApproved));
+        
+        let approve = || accounts::ApproveProposal { mm_wallet: wallet, multisig, proposal, owner: alice };
+        ledger.process(approve(), instruction::ApproveProposal).unwrap();
+        assert_eq!(ledger.process(approve(), instruction::ApproveProposal), custom(MmWalletError::AlreadyApproved));
+        
+        // The proposal only runs the exact action it approved
+        let other = ledger.signer();
+        assert_eq!(
+            ledger.process(owner_action(bob, proposal), instruction::ProposeOwner { new_owner: other }),
+            custom(MmWalletError::ProposalMismatch)
+        );
+        
+        // Any multisig owner can execute it, once
+        ledger.process(owner_action(bob, proposal), propose()).unwrap();
+        assert_eq!(ledger.get::<MmWallet>(&wallet).pending_owner, nominee);
+        assert!(ledger.get::<Proposal>(&proposal).executed);
+        assert_eq!(ledger.process(owner_action(bob, proposal), propose()), custom(MmWalletError::ProposalAlreadyExecuted));
+        
+        // Outsiders can't execute proposals
+        let stranger = ledger.signer();
+        let resume = put_proposal(&mut ledger, wallet, 1, ProposalAction::Resume);
+        assert_eq!(ledger.process(owner_action(stranger, resume), instruction::Resume), custom(MmWalletError::NotMultisigOwner));
+        
+        // The nominee accepts; the multisig keeps governing
+        ledger.process(accounts::AcceptOwner { mm_wallet: wallet, new_owner: nominee }, instruction::AcceptOwner).unwrap();
+        let state: MmWallet = ledger.get(&wallet);
+        assert_eq!(state.owner, nominee);
+        assert!(state.multisig);
+    }
+    
+    #[test]
+    fn test_multisig_applies_queued_config_change() {
+        let queued_at = 1_000;
+        let eta = queued_at + CONFIG_CHANGE_DELAY_SECONDS;
+        let (mut ledger, wallet, multisig, [owner, alice, _]) = multisig_wallet(eta);
+        
+        let operator = Pubkey::new_unique();
+        let (pending_change, bump) = pda(&[b"config_change", wallet.as_ref()]);
+        let pending = PendingConfigChange {
+            wallet,
+            bump,
+            change: ConfigChange::Operator { operator },
+            queued_at,
+            eta,
+            reserved: [0; 32],
+        };
+        ledger.put(pending_change, &pending, PendingConfigChange::SIZE);
+        let apply = |proposal| accounts::ApplyConfigChange {
+            mm_wallet: wallet,
+            pending_change,
+            protocol_config: None,
+            operator_registry: None,
+            session: None,
+            owner,
+            system_program: system_program::ID,
+            multisig: Some(multisig),
+            proposal,
+        };
+        
+        // Applying needs its own proposal, bound to the queued change
+        assert_eq!(ledger.process(apply(None), instruction::ApplyConfigChange), custom(MmWalletError::MultisigRequired));
+        let stale = put_proposal(&mut ledger, wallet, 0, ProposalAction::ApplyConfigChange { queued_at: queued_at - 1 });
+        let approve = accounts::ApproveProposal { mm_wallet: wallet, multisig, proposal: stale, owner: alice };
+        ledger.process(approve, instruction::ApproveProposal).unwrap();
+        assert_eq!(ledger.process(apply(Some(stale)), instruction::ApplyConfigChange), custom(MmWalletError::ProposalMismatch));
+        
+        let proposal = put_proposal(&mut ledger, wallet, 1, ProposalAction::ApplyConfigChange { queued_at });
+        let approve = accounts::ApproveProposal { mm_wallet: wallet, multisig, proposal, owner: alice };
+        ledger.process(approve, instruction::ApproveProposal).unwrap();
+        ledger.process(apply(Some(proposal)), instruction::ApplyConfigChange).unwrap();
+        
+        assert_eq!(ledger.get::<MmWallet>(&wallet).operator, operator);
+        assert!(!ledger.is_open(&pending_change));
+    }
+    
+    #[test]
+    fn test_owner_instructions_without_multisig() {
+        let mut ledger = TestLedger::new(1_000);
+        let owner = ledger.signer();
+        let wallet = Pubkey::new_unique();
+        ledger.put(wallet, &MmWallet { owner, paused: true, ..Default::default() }, MmWallet::SIZE);
+        let owner_action = |signer| accounts::OwnerAction { mm_wallet: wallet, owner: signer, multisig: None, proposal: None };
+        
+        // The owner signs alone; anyone else is rejected
+        let stranger = ledger.signer();
+        assert_eq!(ledger.process(owner_action(stranger), instruction::Resume), custom(MmWalletError::Unauthorized));
+        ledger.process(owner_action(owner), instruction::Resume).unwrap();
+        assert!(!ledger.get::<MmWallet>(&wallet).paused);
+    }
+    
+    #[test]
+    fn test_withdrawals_lower_high_water_mark() {
+        let mut ledger = TestLedger::new(1_000);
+        let owner = ledger.signer();
+        let wallet = Pubkey::new_unique();
+        let (pda_wallet, bump) = pda(&[b"mm_wallet", owner.as_ref(), &0u64.to_le_bytes()]);
+        ledger.insert(pda_wallet, MIN_RENT_RESERVE + 2_000_000_000, Vec::new(), system_program::ID, false);
+        let state = MmWallet { owner, seed_owner: owner, bump, principal: 1_000_000_000, risk_limits: true, ..Default::default() };
+        ledger.put(wallet, &state, MmWallet::SIZE);
+        
+        let (risk_key, bump) = pda(&[b"risk_limits", wallet.as_ref()]);
+        let limits = RiskLimits { wallet, bump, high_water_mark: 3_000_000_000, ..risk_limits(RiskLimitsConfig::default()) };
+        ledger.put(risk_key, &limits, RiskLimits::SIZE);
+        let withdraw = |risk_limits| accounts::Withdraw {
+            mm_wallet: wallet,
+            pda_wallet,
+            owner,
+            destination: owner,
+            system_program: system_program::ID,
+            multisig: None,
+            proposal: None,
+            fee_split: None,
+            risk_limits,
+        };
+        
+        // Once initialized, the risk limits have to come along
+        assert_eq!(
+            ledger.process(withdraw(None), instruction::WithdrawProfits { amount: 500_000_000 }),
+            custom(MmWalletError::RiskLimitsRequired)
+        );
+        
+        // Profit withdrawals come off the high-water mark...
+        ledger.process(withdraw(Some(risk_key)), instruction::WithdrawProfits { amount: 500_000_000 }).unwrap();
+        assert_eq!(ledger.get::<RiskLimits>(&risk_key).high_water_mark, 2_500_000_000);
+        
+        // ... and so do principal withdrawals
+        ledger.process(withdraw(Some(risk_key)), instruction::Withdraw { amount: 1_500_000_000 }).unwrap();
+        assert_eq!(ledger.get::<RiskLimits>(&risk_key).high_water_mark, 1_000_000_000);
+        assert_eq!(ledger.get::<MmWallet>(&wallet).principal, 0);
+    }
+    
+    #[test]
+    fn test_fee_split_update_waits_for_pending_fees() {
+        let mut ledger = TestLedger::new(1_000);
+        let owner = ledger.signer();
+        let (dev, marketer) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let wallet = Pubkey::new_unique();
+        ledger.put(wallet, &MmWallet { owner, total_fees_claimed: 1_000, fee_split: true, ..Default::default() }, MmWallet::SIZE);
+        
+        let (fee_split, bump) = pda(&[b"fee_split", wallet.as_ref()]);
+        let mut split = FeeSplit { wallet, bump, recipients: Vec::new(), distributed: 0, reserved: [0; 32] };
+        split.init_table(&[FeeShare { key: dev, bps: 10_000 }], 400).unwrap();
+        ledger.put(fee_split, &split, FeeSplit::SIZE);
+        let update = |shares| {
+            let accounts = accounts::UpdateFeeSplit { mm_wallet: wallet, fee_split, owner, multisig: None, proposal: None };
+            (accounts, instruction::UpdateFeeSplit { shares })
+        };
+        
+        // 600 lamports claimed under the current table are still owed to `dev`
+        let (accounts, ix) = update(vec![FeeShare { key: marketer, bps: 10_000 }]);
+        assert_eq!(ledger.process(accounts, ix), custom(MmWalletError::FeesPendingDistribution));
+        let state: FeeSplit = ledger.get(&fee_split);
+        assert_eq!((state.recipients[0].key, state.undistributed(1_000)), (dev, 600));
+        
+        // Once they are paid out the table can change
+        split.record_payout(&[600]);
+        ledger.put(fee_split, &split, FeeSplit::SIZE);
+        let (accounts, ix) = update(vec![FeeShare { key: marketer, bps: 10_000 }]);
+        ledger.process(accounts, ix).unwrap();
+        assert_eq!(ledger.get::<FeeSplit>(&fee_split).recipients[0].key, marketer);
+    }
+    
+    #[test]
+    fn test_withdraw_leaves_split_fees() {
+        let sol = 1_000_000_000u64;
+        let mut ledger = TestLedger::new(1_000);
+        let owner = ledger.signer();
+        let wallet = Pubkey::new_unique();
+        let (pda_wallet, bump) = pda(&[b"mm_wallet", owner.as_ref(), &0u64.to_le_bytes()]);
+        
+        // 2 SOL principal, 1 SOL of claimed fees owed to the split and 0.5 SOL of trading profit
+        ledger.insert(pda_wallet, MIN_RENT_RESERVE + 3 * sol + sol / 2, Vec::new(), system_program::ID, false);
+        let state = MmWallet {
+            owner,
+            seed_owner: owner,
+            bump,
+            principal: 2 * sol,
+            total_fees_claimed: sol,
+            fee_split: true,
+            ..Default::default()
+        };
+        ledger.put(wallet, &state, MmWallet::SIZE);
+        
+        let (fee_split, bump) = pda(&[b"fee_split", wallet.as_ref()]);
+        let mut split = FeeSplit { wallet, bump, recipients: Vec::new(), distributed: 0, reserved: [0; 32] };
+        split.init_table(&[FeeShare { key: Pubkey::new_unique(), bps: 10_000 }], 0).unwrap();
+        ledger.put(fee_split, &split, FeeSplit::SIZE);
+        let withdraw = |fee_split| accounts::Withdraw {
+            mm_wallet: wallet,
+            pda_wallet,
+            owner,
+            destination: owner,
+            system_program: system_program::ID,
+            multisig: None,
+            proposal: None,
+            fee_split,
+            risk_limits: None,
+        };
+        
+        // The split has to come along, and its fees can't be withdrawn
+        let ix = |amount| instruction::Withdraw { amount };
+        assert_eq!(ledger.process(withdraw(None), ix(sol)), custom(MmWalletError::FeeSplitRequired));
+        assert_eq!(
+            ledger.process(withdraw(Some(fee_split)), ix(2 * sol + sol / 2 + 1)),
+            custom(MmWalletError::FeesPendingDistribution)
+        );
+        
+        // Everything else can: the profit first, then the principal
+        ledger.process(withdraw(Some(fee_split)), ix(2 * sol + sol / 2)).unwrap();
+        let state: MmWallet = ledger.get(&wallet);
+        assert_eq!(state.principal, 0);
+        assert_eq!(ledger.accounts[&pda_wallet].lamports, MIN_RENT_RESERVE + sol);
+        
+        // What is left is still there for `distribute_fees`
+        assert_eq!(split.undistributed(state.total_fees_claimed).min(state.withdrawable_profit(MIN_RENT_RESERVE + sol)), sol);
+    }
+    
+    #[test]
+    fn test_withdraw_tokens_binds_token_accounts() {
+        use anchor_spl::token::spl_token::state::{Account as SplAccount, AccountState, Mint as SplMint};
+        use anchor_lang::solana_program::program_pack::Pack;
+        
+        let mut ledger = TestLedger::new(1_000);
+        let owner = ledger.signer();
+        let (mint, other_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let wallet = Pubkey::new_unique();
+        let (pda_wallet, bump) = pda(&[b"mm_wallet", owner.as_ref(), &0u64.to_le_bytes()]);
+        ledger.put(wallet, &MmWallet { owner, seed_owner: owner, bump, token_mint: mint, ..Default::default() }, MmWallet::SIZE);
+        
+        ledger.insert(pda_wallet, MIN_RENT_RESERVE, Vec::new(), system_program::ID, false);
+        ledger.insert(token::ID, 1, Vec::new(), Pubkey::default(), true);
+        let mut data = vec![0; SplMint::LEN];
+        SplMint { is_initialized: true, ..Default::default() }.pack_into_slice(&mut data);
+        ledger.insert(mint, 1, data, token::ID, false);
+        let mut token_account = |mint, owner| {
+            let key = Pubkey::new_unique();
+            let mut data = vec![0; SplAccount::LEN];
+            SplAccount { mint, owner, amount: 1_000, state: AccountState::Initialized, ..Default::default() }
+                .pack_into_slice(&mut data);
+            ledger.insert(key, 1, data, token::ID, false);
+            key
+        };
+        let pda_tokens = token_account(mint, pda_wallet);
+        let foreign_tokens = token_account(mint, Pubkey::new_unique());
+        let owner_tokens = token_account(mint, owner);
+        let owner_other_tokens = token_account(other_mint, owner);
+        
+        let withdraw = |pda_token_account, owner_token_account| accounts::WithdrawTokens {
+            mm_wallet: wallet,
+            pda_wallet,
+            pda_token_account,
+            owner_token_account,
+            token_mint: mint,
+            owner,
+            token_program: token::ID,
+            multisig: None,
+            proposal: None,
+        };
+        let ix = || instruction::WithdrawTokens { amount: 0 };
+        
+        // The source must be the PDA's account for the wallet's mint...
+        assert_eq!(
+            ledger.process(withdraw(foreign_tokens, owner_tokens), ix()),
+            custom(MmWalletError::InvalidTokenAccount)
+        );
+        
+        // ... and the destination must hold the same mint
+        assert_eq!(
+            ledger.process(withdraw(pda_tokens, owner_other_tokens), ix()),
+            custom(MmWalletError::TokenMintMismatch)
+        );
+    }
+    
+    #[test]
+    fn test_locked_wallet_queues_session() {
+        let queued_at = 1_000;
+        let mut ledger = TestLedger::new(queued_at);
+        let owner = ledger.signer();
+        let wallet = Pubkey::new_unique();
+        ledger.put(wallet, &MmWallet { owner, lock_until: 1_000_000, ..Default::default() }, MmWallet::SIZE);
+        
+        let session_key = Pubkey::new_unique();
+        let (session, _) = pda(&[b"session", wallet.as_ref(), session_key.as_ref()]);
+        ledger.reserve(session, SessionKey::SIZE);
+        let (roles, expires_at, allowance_lamports) = (ROLE_BUY, queued_at + 7 * 24 * 60 * 60, 1_000_000_000);
+        
+        // Issuing the session directly would skip the delay new operators wait out
+        let create = accounts::CreateSession {
+            mm_wallet: wallet,
+            session,
+            owner,
+            system_program: system_program::ID,
+            multisig: None,
+            proposal: None,
+        };
+        let ix = instruction::CreateSession { session_key, roles, expires_at, expires_at_slot: 0, allowance_lamports };
+        assert_eq!(ledger.process(create, ix), custom(MmWalletError::ConfigChangeTimelocked));
+        
+        // Queued instead, it is issued once the delay has elapsed
+        let (pending_change, _) = pda(&[b"config_change", wallet.as_ref()]);
+        ledger.reserve(pending_change, PendingConfigChange::SIZE);
+        let queue = accounts::QueueConfigChange {
+            mm_wallet: wallet,
+            pending_change,
+            protocol_config: None,
+            owner,
+            system_program: system_program::ID,
+            multisig: None,
+            proposal: None,
+        };
+        let change = ConfigChange::CreateSession { session_key, roles, expires_at, expires_at_slot: 0, allowance_lamports };
+        ledger.process(queue, instruction::QueueConfigChange { change }).unwrap();
+        
+        let apply = |session| accounts::ApplyConfigChange {
+            mm_wallet: wallet,
+            pending_change,
+            protocol_config: None,
+            operator_registry: None,
+            session,
+            owner,
+            system_program: system_program::ID,
+            multisig: None,
+            proposal: None,
+        };
+        assert_eq!(
+            ledger.process(apply(Some(session)), instruction::ApplyConfigChange),
+            custom(MmWalletError::ConfigChangeNotReady)
+        );
+        
+        let eta = queued_at + CONFIG_CHANGE_DELAY_SECONDS;
+        NOW.with(|n| n.set(eta));
+        assert_eq!(
+            ledger.process(apply(None), instruction::ApplyConfigChange),
+            custom(MmWalletError::SessionAccountRequired)
+        );
+        ledger.process(apply(Some(session)), instruction::ApplyConfigChange).unwrap();
+        
+        let issued: SessionKey = ledger.get(&session);
+        assert_eq!((issued.wallet, issued.key, issued.roles), (wallet, session_key, roles));
+        assert_eq!((issued.expires_at, issued.allowance_lamports), (expires_at, allowance_lamports));
+        assert_eq!(issued.created_at, eta);
+        assert!(!ledger.is_open(&pending_change));
+    }
+    
+    #[test]
+    fn test_pda_multisig_owner_runs_gated_instruction() {
+        use anchor_spl::token::spl_token::state::Mint as SplMint;
+        use anchor_lang::solana_program::program_pack::Pack;
+        
+        let (mut ledger, wallet, multisig, [owner, _, _]) = multisig_wallet(1_000);
+        
+        // A DAO program's PDA (off the ed25519 curve) sits in the owner set
+        let dao = Pubkey::find_program_address(&[b"dao"], &Pubkey::new_unique()).0;
+        assert!(!dao.is_on_curve());
+        ledger.insert(dao, 1_000_000_000, Vec::new(), system_program::ID, false);
+        let mut owner_set: OwnerMultisig = ledger.get(&multisig);
+        owner_set.owners[2] = dao;
+        ledger.put(multisig, &owner_set, OwnerMultisig::SIZE);
+        
+        let mint = Pubkey::new_unique();
+        let mut data = vec![0; SplMint::LEN];
+        SplMint { is_initialized: true, ..Default::default() }.pack_into_slice(&mut data);
+        ledger.insert(mint, 1, data, token::ID, false);
+        
+        let set_mint = |signer, proposal| accounts::SetTokenMint {
+            mm_wallet: wallet,
+            token_mint_account: mint,
+            owner: signer,
+            multisig: Some(multisig),
+            proposal,
+        };
+        
+        // The owner key alone can't set the mint
+        assert_eq!(ledger.process(set_mint(owner, None), instruction::SetTokenMint), custom(MmWalletError::MultisigRequired));
+        
+        // The PDA approves (via CPI in practice) and executes the proposal
+        let proposal = put_proposal(&mut ledger, wallet, 0, ProposalAction::SetTokenMint { mint });
+        let approve = accounts::ApproveProposal { mm_wallet: wallet, multisig, proposal, owner: dao };
+        ledger.process(approve, instruction::ApproveProposal).unwrap();
+        
+        ledger.process(set_mint(dao, Some(proposal)), instruction::SetTokenMint).unwrap();
+        assert_eq!(ledger.get::<MmWallet>(&wallet).token_mint, mint);
+        assert!(ledger.get::<Proposal>(&proposal).executed);
     }
 }
 
@@ -0,0 +1,324 @@
+// ═══════════════════════════════════════════════════════════════════════════════
+// MULTISIG
+// Optional M-of-N ownership: sensitive owner actions run from approved proposals
+// ═══════════════════════════════════════════════════════════════════════════════
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+
+use crate::fee_split::FeeShare;
+use crate::operators::OperatorEntry;
+use crate::recovery::MAX_RECOVERY_GUARDIANS;
+use crate::risk::RiskLimitsConfig;
+use crate::vesting::VestingParams;
+use crate::{MmWalletError, Strategy, StrategyConfig};
+
+/// Maximum owner keys per multisig (approvals are a `u16` bitmask)
+pub const MAX_MULTISIG_OWNERS: usize = 10;
+
+/// Kind codes recorded in proposal events
+pub mod proposal_kind {
+    pub const WITHDRAW: u8 = 0;
+    pub const WITHDRAW_PROFITS: u8 = 1;
+    pub const WITHDRAW_TOKENS: u8 = 2;
+    pub const SET_OPERATOR: u8 = 3;
+    pub const UPDATE_STRATEGY: u8 = 4;
+    pub const EXTEND_LOCK: u8 = 5;
+    pub const UPDATE_MULTISIG: u8 = 6;
+    pub const TRANSFER_OWNERSHIP: u8 = 7;
+    pub const ADD_OPERATOR: u8 = 8;
+    pub const UPDATE_OPERATOR: u8 = 9;
+    pub const CREATE_SESSION: u8 = 10;
+    pub const UPDATE_FEE_SPLIT: u8 = 11;
+    pub const UPDATE_RECOVERY: u8 = 12;
+    pub const SET_GUARDIAN: u8 = 13;
+    pub const APPLY_CONFIG_CHANGE: u8 = 14;
+    pub const RESUME: u8 = 15;
+    pub const RESET_CIRCUIT_BREAKER: u8 = 16;
+    pub const UPDATE_RISK_LIMITS: u8 = 17;
+    pub const SET_TOKEN_LOCK: u8 = 18;
+    pub const EXTEND_TOKEN_LOCK: u8 = 19;
+    pub const INIT_OPERATOR_REGISTRY: u8 = 20;
+    pub const CREATE_TOKEN: u8 = 21;
+    pub const SET_TOKEN_MINT: u8 = 22;
+}
+
+/// An owner action gated by the multisig, with the exact arguments approved
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum ProposalAction {
+    /// `withdraw`
+    Withdraw { amount: u64 },
+    
+    /// `withdraw_profits`
+    WithdrawProfits { amount: u64 },
+    
+    /// `withdraw_tokens` into `destination` (a token account)
+    WithdrawTokens { amount: u64, destination: Pubkey },
+    
+    /// `set_operator`, or `queue_config_change` with an operator change
+    SetOperator { operator: Pubkey },
+    
+    /// `update_strategy`, or `queue_config_change` with a strategy change
+    UpdateStrategy { strategy: Strategy, config: StrategyConfig },
+    
+    /// `extend_lock`
+    ExtendLock { additional_seconds: i64 },
+    
+    /// `update_multisig`
+    UpdateMultisig { owners: Vec<Pubkey>, threshold: u8 },
+    
+    /// `propose_owner`
+    TransferOwnership { new_owner: Pubkey },
+    
+    /// `add_operator`, or `queue_config_change` with an operator registry addition
+    AddOperator { entry: OperatorEntry },
+    
+    /// `update_operator`, or `queue_config_change` with an operator registry update
+    UpdateOperator { entry: OperatorEntry },
+    
+    /// `create_session`
+    CreateSession {
+        session_key: Pubkey,
+        roles: u8,
+        expires_at: i64,
+        expires_at_slot: u64,
+        allowance_lamports: u64,
+    },
+    
+    /// `init_fee_split` or `update_fee_split`
+    UpdateFeeSplit { shares: Vec<FeeShare> },
+    
+    /// `init_recovery` or `update_recovery`
+    UpdateRecovery { guardians: Vec<Pubkey>, threshold: u8, challenge_seconds: i64 },
+    
+    /// `set_guardian`
+    SetGuardian { guardian: Pubkey },
+    
+    /// `apply_config_change` for the change queued at `queued_at`
+    ApplyConfigChange { queued_at: i64 },
+    
+    /// `resume`
+    Resume,
+    
+    /// `reset_circuit_breaker`
+    ResetCircuitBreaker,
+    
+    /// `init_risk_limits` or `update_risk_limits`
+    UpdateRiskLimits { limits: RiskLimitsConfig },
+    
+    /// `set_token_lock`
+    SetTokenLock { params: VestingParams },
+    
+    /// `extend_token_lock`
+    ExtendTokenLock { additional_seconds: i64 },
+    
+    /// `init_operator_registry`
+    InitOperatorRegistry,
+    
+    /// `create_token` with the metadata hashing to `metadata_hash` (see `token_metadata_hash`)
+    CreateToken { metadata_hash: [u8; 32] },
+    
+    /// `set_token_mint`
+    SetTokenMint { mint: Pubkey },
+}
+
+impl ProposalAction {
+    /// Largest variant: `UpdateRecovery` with a full guardian set
+    pub const SIZE: usize = 1 + 4 + 32 * MAX_RECOVERY_GUARDIANS + 1 + 8;
+    
+    pub fn kind(&self) -> u8 {
+        match self {
+            ProposalAction::Withdraw { .. } => proposal_kind::WITHDRAW,
+            ProposalAction::WithdrawProfits { .. } => proposal_kind::WITHDRAW_PROFITS,
+            ProposalAction::WithdrawTokens { .. } => proposal_kind::WITHDRAW_TOKENS,
+            ProposalAction::SetOperator { .. } => proposal_kind::SET_OPERATOR,
+            ProposalAction::UpdateStrategy { .. } => proposal_kind::UPDATE_STRATEGY,
+            ProposalAction::ExtendLock { .. } => proposal_kind::EXTEND_LOCK,
+            ProposalAction::UpdateMultisig { .. } => proposal_kind::UPDATE_MULTISIG,
+            ProposalAction::TransferOwnership { .. } => proposal_kind::TRANSFER_OWNERSHIP,
+            ProposalAction::AddOperator { .. } => proposal_kind::ADD_OPERATOR,
+            ProposalAction::UpdateOperator { .. } => proposal_kind::UPDATE_OPERATOR,
+            ProposalAction::CreateSession { .. } => proposal_kind::CREATE_SESSION,
+            ProposalAction::UpdateFeeSplit { .. } => proposal_kind::UPDATE_FEE_SPLIT,
+            ProposalAction::UpdateRecovery { .. } => proposal_kind::UPDATE_RECOVERY,
+            ProposalAction::SetGuardian { .. } => proposal_kind::SET_GUARDIAN,
+            ProposalAction::ApplyConfigChange { .. } => proposal_kind::APPLY_CONFIG_CHANGE,
+            ProposalAction::Resume => proposal_kind::RESUME,
+            ProposalAction::ResetCircuitBreaker => proposal_kind::RESET_CIRCUIT_BREAKER,
+            ProposalAction::UpdateRiskLimits { .. } => proposal_kind::UPDATE_RISK_LIMITS,
+            ProposalAction::SetTokenLock { .. } => proposal_kind::SET_TOKEN_LOCK,
+            ProposalAction::ExtendTokenLock { .. } => proposal_kind::EXTEND_TOKEN_LOCK,
+            ProposalAction::InitOperatorRegistry => proposal_kind::INIT_OPERATOR_REGISTRY,
+            ProposalAction::CreateToken { .. } => proposal_kind::CREATE_TOKEN,
+            ProposalAction::SetTokenMint { .. } => proposal_kind::SET_TOKEN_MINT,
+        }
+    }
+}
+
+/// sha256 of `create_token`'s name, symbol and uri, each length-prefixed as in the
+/// Pump.fun instruction data (a proposal can't hold the strings themselves)
+pub fn token_metadata_hash(name: &str, symbol: &str, uri: &str) -> [u8; 32] {
+    let (name_len, symbol_len, uri_len) = (
+        (name.len() as u32).to_le_bytes(),
+        (symbol.len() as u32).to_le_bytes(),
+        (uri.len() as u32).to_le_bytes(),
+    );
+    
+    hashv(&[&name_len, name.as_bytes(), &symbol_len, symbol.as_bytes(), &uri_len, uri.as_bytes()]).to_bytes()
+}
+
+/// A wallet's owner set and approval threshold
+#[account]
+pub struct OwnerMultisig {
+    /// MM wallet this multisig owns
+    pub wallet: Pubkey,
+    
+    /// PDA bump seed
+    pub bump: u8,
+    
+    /// Owner keys (wallets, or PDAs of DAO and multisig programs signing via CPI)
+    pub owners: Vec<Pubkey>,
+    
+    /// Approvals needed to execute a proposal
+    pub threshold: u8,
+    
+    /// Bumped on every owner set change; older proposals can no longer execute
+    pub generation: u32,
+    
+    /// Proposals created so far (index of the next one)
+    pub proposal_count: u64,
+    
+    /// Reserved space for future upgrades
+    pub reserved: [u8; 32],
+}
+
+impl OwnerMultisig {
+    pub const SIZE: usize = 8 +  // discriminator
+        32 +  // wallet
+        1 +   // bump
+        4 + 32 * MAX_MULTISIG_OWNERS +  // owners
+        1 +   // threshold
+        4 +   // generation
+        8 +   // proposal_count
+        32;   // reserved
+    
+    pub fn validate_owners(owners: &[Pubkey], threshold: u8) -> Result<()> {
+        require!(
+            !owners.is_empty() && owners.len() <= MAX_MULTISIG_OWNERS,
+            MmWalletError::InvalidMultisig
+        );
+        require!(
+            threshold >= 1 && threshold as usize <= owners.len(),
+            MmWalletError::InvalidMultisig
+        );
+        for (i, owner) in owners.iter().enumerate() {
+            require!(*owner != Pubkey::default(), MmWalletError::InvalidMultisig);
+            require!(!owners[..i].contains(owner), MmWalletError::InvalidMultisig);
+        }
+        
+        Ok(())
+    }
+    
+    /// Replace the owner set, invalidating every pending proposal
+    pub fn set_owners(&mut self, owners: Vec<Pubkey>, threshold: u8) -> Result<()> {
+        Self::validate_owners(&owners, threshold)?;
+        
+        self.owners = owners;
+        self.threshold = threshold;
+        self.generation = self.generation.wrapping_add(1);
+        
+        Ok(())
+    }
+    
+    /// Position of `key` in the owner set
+    pub fn owner_index(&self, key: &Pubkey) -> Result<usize> {
+        self.owners
+            .iter()
+            .position(|owner| owner == key)
+            .ok_or(error!(MmWalletError::NotMultisigOwner))
+    }
+}
+
+/// A proposed owner action collecting approvals
+#[account]
+pub struct Proposal {
+    /// MM wallet the action applies to
+    pub wallet: Pubkey,
+    
+    /// Index from `OwnerMultisig.proposal_count` (part of the PDA seeds)
+    pub index: u64,
+    
+    /// PDA bump seed
+    pub bump: u8,
+    
+    /// Multisig owner that created the proposal (gets the rent back on close)
+    pub proposer: Pubkey,
+    
+    /// The action to run
+    pub action: ProposalAction,
+    
+    /// `OwnerMultisig.generation` at creation
+    pub generation: u32,
+    
+    /// Bitmask of approving owners, by position in `OwnerMultisig.owners`
+    pub approvals: u16,
+    
+    /// Set once the action has run
+    pub executed: bool,
+    
+    /// Creation timestamp
+    pub created_at: i64,
+    
+    /// Reserved space for future upgrades
+    pub reserved: [u8; 16],
+}
+
+impl Proposal {
+    pub const SIZE: usize = 8 +  // discriminator
+        32 +  // wallet
+        8 +   // index
+        1 +   // bump
+        32 +  // proposer
+        ProposalAction::SIZE +  // action
+        4 +   // generation
+        2 +   // approvals
+        1 +   // executed
+        8 +   // created_at
+        16;   // reserved
+    
+    pub fn approval_count(&self) -> u32 {
+        self.approvals.count_ones()
+    }
+    
+    /// Record `owner`'s approval, returning the new approval count
+    pub fn approve(&mut self, multisig: &OwnerMultisig, owner: &Pubkey) -> Result<u32> {
+        self.check_live(multisig)?;
+        
+        let bit = 1u16 << multisig.owner_index(owner)?;
+        require!(self.approvals & bit == 0, MmWalletError::AlreadyApproved);
+        self.approvals |= bit;
+        
+        Ok(self.approval_count())
+    }
+    
+    /// Mark the proposal executed if it approves exactly `action`
+    pub fn execute(&mut self, multisig: &OwnerMultisig, action: &ProposalAction) -> Result<()> {
+        self.check_live(multisig)?;
+        require!(
+            self.approval_count() >= multisig.threshold as u32,
+            MmWalletError::ProposalNotApproved
+        );
+        require!(self.action == *action, MmWalletError::ProposalMismatch);
+        
+        self.executed = true;
+        
+        Ok(())
+    }
+    
+    fn check_live(&self, multisig: &OwnerMultisig) -> Result<()> {
+        require!(!self.executed, MmWalletError::ProposalAlreadyExecuted);
+        require!(self.generation == multisig.generation, MmWalletError::ProposalStale);
+        
+        Ok(())
+    }
+}
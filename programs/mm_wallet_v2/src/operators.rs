@@ -0,0 +1,171 @@
+// ═══════════════════════════════════════════════════════════════════════════════
+// OPERATORS
+// Per-wallet registry of operator keys with role bitmasks and trade ceilings
+// ═══════════════════════════════════════════════════════════════════════════════
+
+use anchor_lang::prelude::*;
+
+use crate::{Fill, MmWalletError, MAX_SLIPPAGE_BPS, MIN_SLIPPAGE_BPS};
+
+// Role bits
+pub const ROLE_BUY: u8 = 1 << 0;
+pub const ROLE_SELL: u8 = 1 << 1;
+pub const ROLE_SWAP: u8 = 1 << 2;
+pub const ROLE_CLAIM_FEES: u8 = 1 << 3;
+pub const ROLE_PAUSE: u8 = 1 << 4;
+
+/// Every role
+pub const ROLE_ALL: u8 = ROLE_BUY | ROLE_SELL | ROLE_SWAP | ROLE_CLAIM_FEES | ROLE_PAUSE;
+
+/// Roles of the legacy `MmWallet.operator` (what it could do before the registry)
+pub const ROLE_LEGACY_OPERATOR: u8 = ROLE_BUY | ROLE_SELL | ROLE_SWAP | ROLE_CLAIM_FEES;
+
+/// Maximum operators per wallet
+pub const MAX_OPERATORS: usize = 16;
+
+/// A registered operator
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct OperatorEntry {
+    /// Operator signing key
+    pub key: Pubkey,
+    
+    /// Role bitmask (`ROLE_*`)
+    pub roles: u8,
+    
+    /// Slippage ceiling in bps, tightening the wallet's `slippage_bps` (0 = wallet setting)
+    pub max_slippage_bps: u16,
+    
+    /// Max lamports on the SOL leg of a single trade (0 = unlimited)
+    pub max_trade_lamports: u64,
+}
+
+impl OperatorEntry {
+    pub const SIZE: usize = 32 + 1 + 2 + 8;
+    
+    pub fn validate(&self) -> Result<()> {
+        require!(self.key != Pubkey::default(), MmWalletError::InvalidOperator);
+        require!(
+            self.roles != 0 && self.roles & !ROLE_ALL == 0,
+            MmWalletError::InvalidRoles
+        );
+        require!(
+            self.max_slippage_bps == 0
+                || (MIN_SLIPPAGE_BPS..=MAX_SLIPPAGE_BPS).contains(&self.max_slippage_bps),
+            MmWalletError::InvalidSlippage
+        );
+        
+        Ok(())
+    }
+}
+
+/// Operators allowed to act on a wallet
+#[account]
+pub struct OperatorRegistry {
+    /// MM wallet this registry belongs to
+    pub wallet: Pubkey,
+    
+    /// PDA bump seed
+    pub bump: u8,
+    
+    /// Registered operators
+    pub operators: Vec<OperatorEntry>,
+    
+    /// Reserved space for future upgrades
+    pub reserved: [u8; 64],
+}
+
+impl OperatorRegistry {
+    pub const SIZE: usize = 8 +  // discriminator
+        32 +  // wallet
+        1 +   // bump
+        4 + OperatorEntry::SIZE * MAX_OPERATORS +  // operators
+        64;   // reserved
+    
+    pub fn find(&self, key: &Pubkey) -> Option<&OperatorEntry> {
+        self.operators.iter().find(|op| op.key == *key)
+    }
+    
+    pub fn add(&mut self, entry: OperatorEntry) -> Result<()> {
+        entry.validate()?;
+        require!(self.find(&entry.key).is_none(), MmWalletError::OperatorAlreadyExists);
+        require!(self.operators.len() < MAX_OPERATORS, MmWalletError::OperatorRegistryFull);
+        
+        self.operators.push(entry);
+        
+        Ok(())
+    }
+    
+    pub fn update(&mut self, entry: OperatorEntry) -> Result<()> {
+        entry.validate()?;
+        let existing = self.operators
+            .iter_mut()
+            .find(|op| op.key == entry.key)
+            .ok_or(error!(MmWalletError::OperatorNotFound))?;
+        
+        *existing = entry;
+        
+        Ok(())
+    }
+    
+    pub fn remove(&mut self, key: &Pubkey) -> Result<()> {
+        let index = self.operators
+            .iter()
+            .position(|op| op.key == *key)
+            .ok_or(error!(MmWalletError::OperatorNotFound))?;
+        
+        self.operators.swap_remove(index);
+        
+        Ok(())
+    }
+}
+
+/// What a signer may do on a wallet, resolved from the owner, legacy operator or registry
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Permissions {
+    pub roles: u8,
+    pub max_slippage_bps: u16,
+    pub max_trade_lamports: u64,
+}
+
+impl Permissions {
+    /// Owner: every role, no ceilings
+    pub const OWNER: Self = Self {
+        roles: ROLE_ALL,
+        max_slippage_bps: 0,
+        max_trade_lamports: 0,
+    };
+    
+    /// Legacy `MmWallet.operator`: trade and claim roles, no ceilings
+    pub const LEGACY_OPERATOR: Self = Self {
+        roles: ROLE_LEGACY_OPERATOR,
+        max_slippage_bps: 0,
+        max_trade_lamports: 0,
+    };
+    
+    /// Require every bit in `roles`
+    pub fn require(&self, roles: u8) -> Result<()> {
+        require!(self.roles & roles == roles, MmWalletError::MissingRole);
+        
+        Ok(())
+    }
+    
+    /// Enforce the per-operator size ceiling on a verified fill
+    pub fn check_fill(&self, fill: &Fill) -> Result<()> {
+        require!(
+            self.max_trade_lamports == 0 || fill.sol_amount() <= self.max_trade_lamports,
+            MmWalletError::OperatorTradeCapExceeded
+        );
+        
+        Ok(())
+    }
+}
+
+impl From<&OperatorEntry> for Permissions {
+    fn from(entry: &OperatorEntry) -> Self {
+        Self {
+            roles: entry.roles,
+            max_slippage_bps: entry.max_slippage_bps,
+            max_trade_lamports: entry.max_trade_lamports,
+        }
+    }
+}
@@ -0,0 +1,194 @@
+// ═══════════════════════════════════════════════════════════════════════════════
+// PROTOCOL CONFIG
+// Singleton admin account: global kill switch, venue allowlist and config bounds
+// ═══════════════════════════════════════════════════════════════════════════════
+
+use anchor_lang::prelude::*;
+
+use crate::{MmWalletError, StrategyConfig, MAX_SLIPPAGE_BPS, MAX_TRADE_PCT, MIN_SLIPPAGE_BPS};
+
+/// Maximum number of allowlisted external programs
+pub const MAX_ALLOWED_PROGRAMS: usize = 8;
+
+/// Number of protocol fee tiers (tier 0 is the default for every wallet)
+pub const FEE_TIERS: usize = 4;
+
+/// Protocol fee ceiling per tier (1%)
+pub const MAX_PROTOCOL_FEE_BPS: u16 = 100;
+
+/// Bounds applied to every wallet's `StrategyConfig`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConfigBounds {
+    /// Max `trade_size_pct`
+    pub max_trade_pct: u8,
+    
+    /// Min `slippage_bps`
+    pub min_slippage_bps: u16,
+    
+    /// Max `slippage_bps`
+    pub max_slippage_bps: u16,
+}
+
+impl ConfigBounds {
+    /// Built-in bounds, used until the admin overrides them
+    pub const DEFAULT: Self = Self {
+        max_trade_pct: MAX_TRADE_PCT,
+        min_slippage_bps: MIN_SLIPPAGE_BPS,
+        max_slippage_bps: MAX_SLIPPAGE_BPS,
+    };
+    
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.max_trade_pct >= 1 && self.max_trade_pct <= 100,
+            MmWalletError::InvalidProtocolBounds
+        );
+        require!(
+            self.min_slippage_bps <= self.max_slippage_bps && self.max_slippage_bps < 10_000,
+            MmWalletError::InvalidProtocolBounds
+        );
+        
+        Ok(())
+    }
+    
+    /// Check a wallet's strategy config against these bounds
+    pub fn check(&self, config: &StrategyConfig) -> Result<()> {
+        require!(
+            config.trade_size_pct >= 1 && config.trade_size_pct <= self.max_trade_pct,
+            MmWalletError::InvalidTradeSize
+        );
+        require!(
+            config.slippage_bps >= self.min_slippage_bps && config.slippage_bps <= self.max_slippage_bps,
+            MmWalletError::InvalidSlippage
+        );
+        
+        Ok(())
+    }
+}
+
+impl Default for ConfigBounds {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Admin-set protocol parameters
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ProtocolConfigParams {
+    /// Key that can trigger the global pause and nothing else (default = none)
+    pub guardian: Pubkey,
+    
+    /// External programs trades and claims may CPI into
+    pub allowed_programs: Vec<Pubkey>,
+    
+    /// Strategy config bounds
+    pub bounds: ConfigBounds,
+    
+    /// Recipient of protocol fees
+    pub treasury: Pubkey,
+    
+    /// Protocol fee in bps of each trade's SOL leg, indexed by `MmWallet.fee_tier`
+    /// (a 0 bps tier is fee-exempt)
+    pub fee_bps_by_tier: [u16; FEE_TIERS],
+}
+
+impl ProtocolConfigParams {
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.allowed_programs.len() <= MAX_ALLOWED_PROGRAMS,
+            MmWalletError::TooManyAllowedPrograms
+        );
+        require!(
+            self.fee_bps_by_tier.iter().all(|bps| *bps <= MAX_PROTOCOL_FEE_BPS),
+            MmWalletError::ProtocolFeeTooHigh
+        );
+        require!(
+            self.treasury != Pubkey::default() || self.fee_bps_by_tier.iter().all(|bps| *bps == 0),
+            MmWalletError::InvalidTreasury
+        );
+        self.bounds.validate()
+    }
+}
+
+/// Protocol-wide configuration (singleton PDA)
+#[account]
+pub struct ProtocolConfig {
+    /// Protocol admin
+    pub admin: Pubkey,
+    
+    /// PDA bump seed
+    pub bump: u8,
+    
+    /// Global kill switch for trades and claims
+    pub paused: bool,
+    
+    /// Key that can trigger the global pause and nothing else (default = none)
+    pub guardian: Pubkey,
+    
+    /// External programs trades and claims may CPI into
+    pub allowed_programs: Vec<Pubkey>,
+    
+    /// Strategy config bounds
+    pub bounds: ConfigBounds,
+    
+    /// Recipient of protocol fees
+    pub treasury: Pubkey,
+    
+    /// Protocol fee in bps of each trade's SOL leg, indexed by `MmWallet.fee_tier`
+    pub fee_bps_by_tier: [u16; FEE_TIERS],
+    
+    /// Reserved space for future upgrades
+    pub reserved: [u8; 24],
+}
+
+impl ProtocolConfig {
+    pub const SIZE: usize = 8 +  // discriminator
+        32 +  // admin
+        1 +   // bump
+        1 +   // paused
+        32 +  // guardian
+        4 + 32 * MAX_ALLOWED_PROGRAMS +  // allowed_programs
+        5 +   // bounds
+        32 +  // treasury
+        2 * FEE_TIERS +  // fee_bps_by_tier
+        24;   // reserved
+    
+    pub fn apply(&mut self, params: ProtocolConfigParams) {
+        self.guardian = params.guardian;
+        self.allowed_programs = params.allowed_programs;
+        self.bounds = params.bounds;
+        self.treasury = params.treasury;
+        self.fee_bps_by_tier = params.fee_bps_by_tier;
+    }
+    
+    /// Check if `key` is the protocol guardian (never true when none is set)
+    pub fn is_guardian(&self, key: &Pubkey) -> bool {
+        self.guardian != Pubkey::default() && *key == self.guardian
+    }
+    
+    /// Global state checks shared by every trade and claim instruction
+    pub fn check_cpi_allowed(&self, program_id: &Pubkey) -> Result<()> {
+        require!(!self.paused, MmWalletError::ProtocolPaused);
+        require!(
+            self.allowed_programs.contains(program_id),
+            MmWalletError::ProgramNotAllowed
+        );
+        
+        Ok(())
+    }
+    
+    /// Protocol fee owed on `notional` lamports by a wallet in `fee_tier`
+    pub fn protocol_fee(&self, fee_tier: u8, notional: u64) -> Result<u64> {
+        let bps = *self
+            .fee_bps_by_tier
+            .get(fee_tier as usize)
+            .ok_or(error!(MmWalletError::InvalidFeeTier))?;
+        
+        Ok(((notional as u128) * (bps as u128) / 10_000) as u64)
+    }
+    
+    /// `check_cpi_allowed` plus the wallet's config against the protocol bounds
+    pub fn check_trade(&self, config: &StrategyConfig, program_id: &Pubkey) -> Result<()> {
+        self.check_cpi_allowed(program_id)?;
+        self.bounds.check(config)
+    }
+}
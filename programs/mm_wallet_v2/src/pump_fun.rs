@@ -0,0 +1,362 @@
+// ═══════════════════════════════════════════════════════════════════════════════
+// PUMP.FUN BONDING CURVE
+// Account parsing and on-chain quoting for the Pump.fun bonding curve
+// ═══════════════════════════════════════════════════════════════════════════════
+
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::TokenAccount;
+
+use crate::{
+    require_remaining_key, venue_instruction, verify_realized, BalanceSnapshot, Fill, MmWallet,
+    MmWalletError, TradeSide, Venue, MIN_RENT_RESERVE, PRICE_SCALE, PUMP_BUY_DISCRIMINATOR,
+    PUMP_FUN_PROGRAM, PUMP_SELL_DISCRIMINATOR,
+};
+
+/// Pump.fun Global account (mainnet) - 4wTV1YmiEkRvAtNtsSGPtUrqRYQMe5SKy2uB4Jjaxnjf
+pub const PUMP_GLOBAL: Pubkey = Pubkey::new_from_array([
+    58, 134, 94, 105, 238, 15, 84, 128, 202, 188, 246, 99, 87, 228, 220, 47,
+    24, 213, 141, 69, 193, 234, 116, 137, 251, 55, 35, 217, 121, 60, 114, 166
+]);
+
+// Account discriminators: sha256("account:<Name>")[0..8]
+pub const BONDING_CURVE_DISCRIMINATOR: [u8; 8] = [23, 183, 248, 55, 96, 216, 172, 96];
+pub const GLOBAL_DISCRIMINATOR: [u8; 8] = [167, 232, 232, 177, 200, 108, 114, 127];
+
+/// Account positions shared by Pump.fun `buy` and `sell`
+pub mod account_index {
+    pub const GLOBAL: usize = 0;
+    pub const MINT: usize = 2;
+    pub const BONDING_CURVE: usize = 3;
+    pub const ASSOCIATED_BONDING_CURVE: usize = 4;
+    pub const ASSOCIATED_USER: usize = 5;
+    pub const USER: usize = 6;
+    pub const PROGRAM: usize = 11;
+}
+
+/// Minimum number of accounts Pump.fun `buy` expects
+pub const BUY_ACCOUNTS_LEN: usize = 16;
+
+/// Minimum number of accounts Pump.fun `sell` expects
+pub const SELL_ACCOUNTS_LEN: usize = 14;
+
+/// Global: discriminator + initialized + authority + fee_recipient + 4 reserve/supply fields
+const GLOBAL_FEE_BPS_OFFSET: usize = 8 + 1 + 32 + 32 + 8 * 4;
+
+/// Global: fee_basis_points + withdraw_authority + enable_migrate + pool_migration_fee
+const GLOBAL_CREATOR_FEE_BPS_OFFSET: usize = GLOBAL_FEE_BPS_OFFSET + 8 + 32 + 1 + 8;
+
+/// Bonding curve reserves (leading fields of the Pump.fun `BondingCurve` account)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BondingCurveState {
+    pub virtual_token_reserves: u64,
+    pub virtual_sol_reserves: u64,
+    pub real_token_reserves: u64,
+    pub real_sol_reserves: u64,
+    pub token_total_supply: u64,
+    pub complete: bool,
+}
+
+impl BondingCurveState {
+    pub const LEN: usize = 8 + 8 * 5 + 1;
+    
+    /// Parse a bonding curve account, checking owner and discriminator
+    pub fn try_from_account(info: &AccountInfo) -> Result<Self> {
+        require!(info.owner == &PUMP_FUN_PROGRAM, MmWalletError::InvalidBondingCurve);
+        
+        let data = info.try_borrow_data()?;
+        Self::try_from_slice_checked(&data)
+    }
+    
+    pub fn try_from_slice_checked(data: &[u8]) -> Result<Self> {
+        require!(
+            data.len() >= Self::LEN && data[..8] == BONDING_CURVE_DISCRIMINATOR,
+            MmWalletError::InvalidBondingCurve
+        );
+        
+        Ok(Self {
+            virtual_token_reserves: read_u64(data, 8),
+            virtual_sol_reserves: read_u64(data, 16),
+            real_token_reserves: read_u64(data, 24),
+            real_sol_reserves: read_u64(data, 32),
+            token_total_supply: read_u64(data, 40),
+            complete: data[48] != 0,
+        })
+    }
+    
+    /// Tokens received for spending `sol_in` lamports (fees included in `sol_in`)
+    pub fn quote_buy(&self, sol_in: u64, fees: &PumpFees) -> Result<u64> {
+        require!(!self.complete, MmWalletError::BondingCurveComplete);
+        
+        let net_sol = net_of_fees_in(sol_in, fees.total_bps())?;
+        let tokens_out = constant_product_out(
+            net_sol,
+            self.virtual_sol_reserves as u128,
+            self.virtual_token_reserves as u128,
+        )?;
+        
+        Ok(tokens_out.min(self.real_token_reserves as u128) as u64)
+    }
+    
+    /// Lamports received for selling `token_in` tokens (after fees)
+    pub fn quote_sell(&self, token_in: u64, fees: &PumpFees) -> Result<u64> {
+        require!(!self.complete, MmWalletError::BondingCurveComplete);
+        
+        let gross_sol = constant_product_out(
+            token_in as u128,
+            self.virtual_token_reserves as u128,
+            self.virtual_sol_reserves as u128,
+        )?;
+        
+        net_of_fees_out(gross_sol, fees.total_bps())
+    }
+    
+    /// Spot price from virtual reserves (lamports per token, scaled by PRICE_SCALE)
+    pub fn spot_price(&self) -> u64 {
+        spot_price(self.virtual_sol_reserves, self.virtual_token_reserves)
+    }
+}
+
+/// Trading fees read from the Pump.fun Global account
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PumpFees {
+    /// Protocol fee in basis points
+    pub fee_bps: u64,
+    
+    /// Creator fee in basis points (0 on Global layouts that predate creator fees)
+    pub creator_fee_bps: u64,
+}
+
+impl PumpFees {
+    /// Parse fees from the Global account, checking address, owner and discriminator
+    pub fn try_from_global(info: &AccountInfo) -> Result<Self> {
+        require!(info.key() == PUMP_GLOBAL, MmWalletError::InvalidPumpGlobal);
+        require!(info.owner == &PUMP_FUN_PROGRAM, MmWalletError::InvalidPumpGlobal);
+        
+        let data = info.try_borrow_data()?;
+        Self::try_from_slice_checked(&data)
+    }
+    
+    pub fn try_from_slice_checked(data: &[u8]) -> Result<Self> {
+        require!(
+            data.len() >= GLOBAL_FEE_BPS_OFFSET + 8 && data[..8] == GLOBAL_DISCRIMINATOR,
+            MmWalletError::InvalidPumpGlobal
+        );
+        
+        let creator_fee_bps = if data.len() >= GLOBAL_CREATOR_FEE_BPS_OFFSET + 8 {
+            read_u64(data, GLOBAL_CREATOR_FEE_BPS_OFFSET)
+        } else {
+            0
+        };
+        
+        Ok(Self {
+            fee_bps: read_u64(data, GLOBAL_FEE_BPS_OFFSET),
+            creator_fee_bps,
+        })
+    }
+    
+    pub fn total_bps(&self) -> u64 {
+        self.fee_bps.saturating_add(self.creator_fee_bps)
+    }
+}
+
+/// `buy(token_amount, max_sol_cost)`: exact tokens out, capped SOL in
+pub fn buy_data(token_amount: u64, max_sol_cost: u64) -> Vec<u8> {
+    let mut data = PUMP_BUY_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&token_amount.to_le_bytes());
+    data.extend_from_slice(&max_sol_cost.to_le_bytes());
+    data
+}
+
+/// `sell(token_amount, min_sol_output)`: exact tokens in, floored SOL out
+pub fn sell_data(token_amount: u64, min_sol_output: u64) -> Vec<u8> {
+    let mut data = PUMP_SELL_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&token_amount.to_le_bytes());
+    data.extend_from_slice(&min_sol_output.to_le_bytes());
+    data
+}
+
+/// Named accounts of a bonding-curve trade
+pub struct CurveTradeAccounts<'a, 'info> {
+    pub pda_wallet: &'a AccountInfo<'info>,
+    pub pda_token_account: &'a mut Account<'info, TokenAccount>,
+    pub bonding_curve: &'a AccountInfo<'info>,
+    pub pump_global: &'a AccountInfo<'info>,
+    pub remaining: &'a [AccountInfo<'info>],
+}
+
+/// Quote, execute and verify a bonding-curve trade signed by the wallet PDA
+/// 
+/// Buys pass `buy(min_tokens_out, amount_in)`, so at most `amount_in` lamports are spent.
+/// Sells pass `sell(amount_in, min_sol_out)`. `max_slippage_bps` is the caller's
+/// slippage ceiling (0 = wallet setting).
+pub fn execute_trade(
+    accounts: CurveTradeAccounts,
+    wallet: &MmWallet,
+    side: TradeSide,
+    amount_in: u64,
+    expected_out: u64,
+    max_slippage_bps: u16,
+) -> Result<Fill> {
+    let is_buy = side == TradeSide::Buy;
+    
+    // ═══ ACCOUNT BINDING ═══
+    validate_trade_accounts(
+        accounts.remaining,
+        &wallet.token_mint,
+        accounts.pda_wallet.key,
+        accounts.bonding_curve.key,
+        &accounts.pda_token_account.key(),
+        if is_buy { BUY_ACCOUNTS_LEN } else { SELL_ACCOUNTS_LEN },
+    )?;
+    
+    // ═══ BALANCE & AMOUNT VALIDATION ═══
+    if is_buy {
+        let available = accounts.pda_wallet.lamports().saturating_sub(MIN_RENT_RESERVE);
+        wallet.check_buy_amount(amount_in, available)?;
+    } else {
+        wallet.check_sell_amount(amount_in, accounts.pda_token_account.amount)?;
+    }
+    
+    // ═══ ON-CHAIN QUOTE ═══
+    let curve = BondingCurveState::try_from_account(accounts.bonding_curve)?;
+    let fees = PumpFees::try_from_global(accounts.pump_global)?;
+    let quoted_out = if is_buy {
+        curve.quote_buy(amount_in, &fees)?
+    } else {
+        curve.quote_sell(amount_in, &fees)?
+    };
+    
+    // ═══ SLIPPAGE PROTECTION (calculated on-chain) ═══
+    let min_amount_out = wallet.min_output_from_quote(expected_out, quoted_out, max_slippage_bps)?;
+    
+    // ═══ BUILD CPI ═══
+    let data = if is_buy {
+        buy_data(min_amount_out, amount_in)
+    } else {
+        sell_data(amount_in, min_amount_out)
+    };
+    let ix = venue_instruction(PUMP_FUN_PROGRAM, accounts.remaining, data);
+    
+    let before = BalanceSnapshot::capture(accounts.pda_wallet, accounts.pda_token_account);
+    
+    // Execute CPI with PDA as signer
+    wallet.invoke_signed(&ix, accounts.remaining)?;
+    
+    // ═══ VERIFY REALIZED AMOUNTS ═══
+    accounts.pda_token_account.reload()?;
+    let after = BalanceSnapshot::capture(accounts.pda_wallet, accounts.pda_token_account);
+    let (realized_in, realized_out) = before.realized(&after, is_buy);
+    verify_realized(realized_in, realized_out, amount_in, min_amount_out)?;
+    
+    // Post-trade curve price for equity valuation
+    let mark_price = BondingCurveState::try_from_account(accounts.bonding_curve)?.spot_price();
+    
+    Ok(Fill {
+        venue: Venue::BondingCurve,
+        side,
+        amount_in,
+        min_amount_out,
+        realized_in,
+        realized_out,
+        mark_price,
+    })
+}
+
+/// Bonding curve PDA for a mint
+pub fn bonding_curve_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"bonding-curve", mint.as_ref()], &PUMP_FUN_PROGRAM).0
+}
+
+/// Validate that the remaining accounts of a Pump.fun trade are bound to the wallet's mint
+/// and follow the venue's account ordering
+pub fn validate_trade_accounts(
+    accounts: &[AccountInfo],
+    mint: &Pubkey,
+    pda_wallet: &Pubkey,
+    bonding_curve: &Pubkey,
+    pda_token_account: &Pubkey,
+    min_len: usize,
+) -> Result<()> {
+    require!(accounts.len() >= min_len, MmWalletError::InvalidRemainingAccounts);
+    
+    let expected_curve = bonding_curve_address(mint);
+    let pda_ata = get_associated_token_address(pda_wallet, mint);
+    require_keys_eq!(*bonding_curve, expected_curve, MmWalletError::TokenMintMismatch);
+    require_keys_eq!(*pda_token_account, pda_ata, MmWalletError::TokenMintMismatch);
+    
+    let mint_bound = [
+        (account_index::MINT, *mint),
+        (account_index::BONDING_CURVE, expected_curve),
+        (
+            account_index::ASSOCIATED_BONDING_CURVE,
+            get_associated_token_address(&expected_curve, mint),
+        ),
+        (account_index::ASSOCIATED_USER, pda_ata),
+    ];
+    for (index, expected) in mint_bound.iter() {
+        require_remaining_key(accounts, *index, expected, MmWalletError::TokenMintMismatch)?;
+    }
+    
+    let layout = [
+        (account_index::GLOBAL, PUMP_GLOBAL),
+        (account_index::USER, *pda_wallet),
+        (account_index::PROGRAM, PUMP_FUN_PROGRAM),
+    ];
+    for (index, expected) in layout.iter() {
+        require_remaining_key(accounts, *index, expected, MmWalletError::InvalidRemainingAccounts)?;
+    }
+    
+    Ok(())
+}
+
+/// Constant-product output: `amount_in * reserve_out / (reserve_in + amount_in)`
+pub fn constant_product_out(amount_in: u128, reserve_in: u128, reserve_out: u128) -> Result<u128> {
+    let denominator = reserve_in
+        .checked_add(amount_in)
+        .ok_or(error!(MmWalletError::MathOverflow))?;
+    require!(denominator > 0, MmWalletError::InvalidBondingCurve);
+    
+    amount_in
+        .checked_mul(reserve_out)
+        .ok_or(error!(MmWalletError::MathOverflow))?
+        .checked_div(denominator)
+        .ok_or(error!(MmWalletError::MathOverflow))
+}
+
+/// Spot price `sol_reserves / token_reserves` (lamports per token, scaled by PRICE_SCALE)
+pub fn spot_price(sol_reserves: u64, token_reserves: u64) -> u64 {
+    if token_reserves == 0 {
+        return 0;
+    }
+    
+    ((sol_reserves as u128) * PRICE_SCALE / (token_reserves as u128)).min(u64::MAX as u128) as u64
+}
+
+/// Input left for the curve after fees charged on top of it
+/// (`amount_in` includes fees, so they are stripped from the budget first)
+pub fn net_of_fees_in(amount_in: u64, fee_bps: u64) -> Result<u128> {
+    (amount_in as u128)
+        .checked_mul(10_000)
+        .ok_or(error!(MmWalletError::MathOverflow))?
+        .checked_div(10_000u128 + fee_bps as u128)
+        .ok_or(error!(MmWalletError::MathOverflow))
+}
+
+/// Output left after fees deducted from it (fees round up, as both venues do)
+pub fn net_of_fees_out(gross_out: u128, fee_bps: u64) -> Result<u64> {
+    let fee = gross_out
+        .checked_mul(fee_bps as u128)
+        .ok_or(error!(MmWalletError::MathOverflow))?
+        .checked_add(9_999)
+        .ok_or(error!(MmWalletError::MathOverflow))?
+        / 10_000;
+    
+    u64::try_from(gross_out.saturating_sub(fee)).map_err(|_| error!(MmWalletError::MathOverflow))
+}
+
+pub(crate) fn read_u64(data: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
@@ -0,0 +1,515 @@
+// ═══════════════════════════════════════════════════════════════════════════════
+// PUMPSWAP AMM
+// Pool parsing, quoting and instruction layouts for migrated tokens
+// ═══════════════════════════════════════════════════════════════════════════════
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::associated_token::{self, get_associated_token_address};
+use anchor_spl::token::spl_token::native_mint;
+use anchor_spl::token::{self, CloseAccount, SyncNative, Token, TokenAccount};
+
+use crate::pump_fun::{constant_product_out, net_of_fees_in, net_of_fees_out, read_u64, spot_price};
+use crate::{
+    require_remaining_key, venue_instruction, verify_realized, BalanceSnapshot, Fill, MmWallet,
+    MmWalletError, TradeSide, Venue, MIN_RENT_RESERVE, PUMPSWAP_PROGRAM, PUMP_FUN_PROGRAM,
+};
+
+/// PumpSwap GlobalConfig (mainnet) - ADyA8hdefvWN2dbGGWFotbzWxrAvLW83WG6QCVXvJKqw
+pub const PUMPSWAP_GLOBAL_CONFIG: Pubkey = Pubkey::new_from_array([
+    137, 11, 166, 68, 254, 31, 85, 170, 25, 241, 28, 210, 210, 236, 20, 211,
+    35, 59, 110, 10, 75, 234, 238, 247, 43, 105, 133, 142, 33, 225, 112, 214
+]);
+
+// Instruction discriminators: sha256("global:<name>")[0..8]
+// PumpSwap is an Anchor program too, so `buy`/`sell` hash to the same bytes as on Pump.fun
+pub const PUMPSWAP_BUY_DISCRIMINATOR: [u8; 8] = [102, 6, 61, 18, 1, 218, 235, 234];
+pub const PUMPSWAP_SELL_DISCRIMINATOR: [u8; 8] = [51, 230, 133, 164, 1, 127, 131, 173];
+pub const COLLECT_COIN_CREATOR_FEE_DISCRIMINATOR: [u8; 8] = [160, 57, 89, 42, 181, 139, 43, 66];
+
+// Account discriminators: sha256("account:<Name>")[0..8]
+pub const POOL_DISCRIMINATOR: [u8; 8] = [241, 154, 109, 4, 17, 177, 109, 188];
+pub const GLOBAL_CONFIG_DISCRIMINATOR: [u8; 8] = [149, 8, 156, 202, 160, 252, 176, 217];
+
+/// Pool: discriminator + pool_bump + index + creator
+const POOL_BASE_MINT_OFFSET: usize = 8 + 1 + 2 + 32;
+
+/// Pool: base_mint + quote_mint + lp_mint + base/quote token accounts + lp_supply
+const POOL_COIN_CREATOR_OFFSET: usize = POOL_BASE_MINT_OFFSET + 32 * 5 + 8;
+
+/// GlobalConfig: discriminator + admin
+const CONFIG_LP_FEE_OFFSET: usize = 8 + 32;
+
+/// GlobalConfig: lp/protocol fees + disable_flags + protocol_fee_recipients[8]
+const CONFIG_CREATOR_FEE_OFFSET: usize = CONFIG_LP_FEE_OFFSET + 8 + 8 + 1 + 32 * 8;
+
+/// SPL token account: mint + owner
+const TOKEN_ACCOUNT_AMOUNT_OFFSET: usize = 32 + 32;
+
+/// Account positions shared by PumpSwap `buy` and `sell`
+pub mod account_index {
+    pub const POOL: usize = 0;
+    pub const USER: usize = 1;
+    pub const GLOBAL_CONFIG: usize = 2;
+    pub const BASE_MINT: usize = 3;
+    pub const QUOTE_MINT: usize = 4;
+    pub const USER_BASE_TOKEN_ACCOUNT: usize = 5;
+    pub const USER_QUOTE_TOKEN_ACCOUNT: usize = 6;
+    pub const POOL_BASE_TOKEN_ACCOUNT: usize = 7;
+    pub const POOL_QUOTE_TOKEN_ACCOUNT: usize = 8;
+    pub const SYSTEM_PROGRAM: usize = 13;
+    pub const ASSOCIATED_TOKEN_PROGRAM: usize = 14;
+    pub const PROGRAM: usize = 16;
+}
+
+/// Minimum number of accounts PumpSwap `buy`/`sell` expect
+pub const SWAP_ACCOUNTS_LEN: usize = 21;
+
+/// Account positions of PumpSwap `collect_coin_creator_fee`
+pub mod creator_fee_index {
+    pub const QUOTE_MINT: usize = 0;
+    pub const QUOTE_TOKEN_PROGRAM: usize = 1;
+    pub const COIN_CREATOR: usize = 2;
+    pub const COIN_CREATOR_VAULT_AUTHORITY: usize = 3;
+    pub const COIN_CREATOR_VAULT_ATA: usize = 4;
+    pub const COIN_CREATOR_TOKEN_ACCOUNT: usize = 5;
+    pub const PROGRAM: usize = 7;
+}
+
+/// Number of accounts PumpSwap `collect_coin_creator_fee` expects
+pub const CREATOR_FEE_ACCOUNTS_LEN: usize = 8;
+
+/// Leading fields of the PumpSwap `Pool` account
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PoolState {
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub pool_base_token_account: Pubkey,
+    pub pool_quote_token_account: Pubkey,
+    pub coin_creator: Pubkey,
+}
+
+impl PoolState {
+    pub const LEN: usize = POOL_COIN_CREATOR_OFFSET + 32;
+    
+    /// Parse a pool account, checking owner and discriminator
+    pub fn try_from_account(info: &AccountInfo) -> Result<Self> {
+        require!(info.owner == &PUMPSWAP_PROGRAM, MmWalletError::InvalidPool);
+        
+        let data = info.try_borrow_data()?;
+        Self::try_from_slice_checked(&data)
+    }
+    
+    pub fn try_from_slice_checked(data: &[u8]) -> Result<Self> {
+        require!(
+            data.len() >= Self::LEN && data[..8] == POOL_DISCRIMINATOR,
+            MmWalletError::InvalidPool
+        );
+        
+        let key_at = |offset: usize| {
+            let mut bytes = [0u8; 32];
+            bytes.copy_from_slice(&data[offset..offset + 32]);
+            Pubkey::new_from_array(bytes)
+        };
+        
+        Ok(Self {
+            base_mint: key_at(POOL_BASE_MINT_OFFSET),
+            quote_mint: key_at(POOL_BASE_MINT_OFFSET + 32),
+            pool_base_token_account: key_at(POOL_BASE_MINT_OFFSET + 32 * 3),
+            pool_quote_token_account: key_at(POOL_BASE_MINT_OFFSET + 32 * 4),
+            coin_creator: key_at(POOL_COIN_CREATOR_OFFSET),
+        })
+    }
+}
+
+/// Trading fees read from the PumpSwap GlobalConfig account
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SwapFees {
+    pub lp_fee_bps: u64,
+    pub protocol_fee_bps: u64,
+    
+    /// Only charged when the pool has a coin creator
+    pub coin_creator_fee_bps: u64,
+}
+
+impl SwapFees {
+    /// Parse fees from GlobalConfig, checking address, owner and discriminator
+    pub fn try_from_global_config(info: &AccountInfo, pool: &PoolState) -> Result<Self> {
+        require!(info.key() == PUMPSWAP_GLOBAL_CONFIG, MmWalletError::InvalidPool);
+        require!(info.owner == &PUMPSWAP_PROGRAM, MmWalletError::InvalidPool);
+        
+        let data = info.try_borrow_data()?;
+        let mut fees = Self::try_from_slice_checked(&data)?;
+        if pool.coin_creator == Pubkey::default() {
+            fees.coin_creator_fee_bps = 0;
+        }
+        
+        Ok(fees)
+    }
+    
+    pub fn try_from_slice_checked(data: &[u8]) -> Result<Self> {
+        require!(
+            data.len() >= CONFIG_LP_FEE_OFFSET + 16 && data[..8] == GLOBAL_CONFIG_DISCRIMINATOR,
+            MmWalletError::InvalidPool
+        );
+        
+        let coin_creator_fee_bps = if data.len() >= CONFIG_CREATOR_FEE_OFFSET + 8 {
+            read_u64(data, CONFIG_CREATOR_FEE_OFFSET)
+        } else {
+            0
+        };
+        
+        Ok(Self {
+            lp_fee_bps: read_u64(data, CONFIG_LP_FEE_OFFSET),
+            protocol_fee_bps: read_u64(data, CONFIG_LP_FEE_OFFSET + 8),
+            coin_creator_fee_bps,
+        })
+    }
+    
+    pub fn total_bps(&self) -> u64 {
+        self.lp_fee_bps
+            .saturating_add(self.protocol_fee_bps)
+            .saturating_add(self.coin_creator_fee_bps)
+    }
+}
+
+/// Pool vault balances (base = token, quote = WSOL)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PoolReserves {
+    pub base: u64,
+    pub quote: u64,
+}
+
+impl PoolReserves {
+    /// Read current balances straight from the pool vaults (SPL token `amount` at offset 64)
+    pub fn from_vaults(base_vault: &AccountInfo, quote_vault: &AccountInfo) -> Result<Self> {
+        Ok(Self {
+            base: read_u64(&base_vault.try_borrow_data()?, TOKEN_ACCOUNT_AMOUNT_OFFSET),
+            quote: read_u64(&quote_vault.try_borrow_data()?, TOKEN_ACCOUNT_AMOUNT_OFFSET),
+        })
+    }
+    
+    /// Tokens received for spending `quote_in` lamports of WSOL (fees included in `quote_in`)
+    pub fn quote_buy(&self, quote_in: u64, fees: &SwapFees) -> Result<u64> {
+        let net_quote = net_of_fees_in(quote_in, fees.total_bps())?;
+        let base_out = constant_product_out(net_quote, self.quote as u128, self.base as u128)?;
+        
+        u64::try_from(base_out).map_err(|_| error!(MmWalletError::MathOverflow))
+    }
+    
+    /// Lamports of WSOL received for selling `base_in` tokens (after fees)
+    pub fn quote_sell(&self, base_in: u64, fees: &SwapFees) -> Result<u64> {
+        let gross_quote = constant_product_out(base_in as u128, self.base as u128, self.quote as u128)?;
+        
+        net_of_fees_out(gross_quote, fees.total_bps())
+    }
+    
+    /// Spot price (lamports per token, scaled by PRICE_SCALE)
+    pub fn spot_price(&self) -> u64 {
+        spot_price(self.quote, self.base)
+    }
+}
+
+/// `buy(base_amount_out, max_quote_amount_in)`: exact tokens out, capped WSOL in
+pub fn buy_data(base_amount_out: u64, max_quote_amount_in: u64) -> Vec<u8> {
+    let mut data = PUMPSWAP_BUY_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&base_amount_out.to_le_bytes());
+    data.extend_from_slice(&max_quote_amount_in.to_le_bytes());
+    data
+}
+
+/// `sell(base_amount_in, min_quote_amount_out)`: exact tokens in, floored WSOL out
+pub fn sell_data(base_amount_in: u64, min_quote_amount_out: u64) -> Vec<u8> {
+    let mut data = PUMPSWAP_SELL_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&base_amount_in.to_le_bytes());
+    data.extend_from_slice(&min_quote_amount_out.to_le_bytes());
+    data
+}
+
+/// Named accounts of a PumpSwap trade
+pub struct SwapTradeAccounts<'a, 'info> {
+    pub pda_wallet: &'a AccountInfo<'info>,
+    pub pda_token_account: &'a mut Account<'info, TokenAccount>,
+    pub pda_quote_account: &'a mut Account<'info, TokenAccount>,
+    pub pool: &'a AccountInfo<'info>,
+    pub pool_base_token_account: &'a Account<'info, TokenAccount>,
+    pub pool_quote_token_account: &'a Account<'info, TokenAccount>,
+    pub global_config: &'a AccountInfo<'info>,
+    pub token_program: &'a Program<'info, Token>,
+    pub remaining: &'a [AccountInfo<'info>],
+}
+
+/// Quote, execute and verify a PumpSwap trade signed by the wallet PDA
+/// 
+/// Buys are exact-out: `buy(min_amount_out, amount_in)` spends at most `amount_in`
+/// lamports of WSOL. Sells are exact-in: `sell(amount_in, min_amount_out)`.
+/// SOL is settled through the PDA's WSOL account; buy shortfalls are wrapped from
+/// the PDA's lamports before the CPI. `max_slippage_bps` is the caller's slippage
+/// ceiling (0 = wallet setting).
+pub fn execute_trade(
+    accounts: SwapTradeAccounts,
+    wallet: &MmWallet,
+    side: TradeSide,
+    amount_in: u64,
+    expected_out: u64,
+    max_slippage_bps: u16,
+) -> Result<Fill> {
+    let is_buy = side == TradeSide::Buy;
+    
+    // ═══ ACCOUNT BINDING ═══
+    let pool = PoolState::try_from_account(accounts.pool)?;
+    validate_swap_accounts(
+        accounts.remaining,
+        &wallet.token_mint,
+        accounts.pda_wallet.key,
+        &accounts.pda_token_account.key(),
+        &accounts.pda_quote_account.key(),
+        accounts.pool.key,
+        &pool,
+    )?;
+    require_keys_eq!(
+        accounts.pool_base_token_account.key(),
+        pool.pool_base_token_account,
+        MmWalletError::InvalidPool
+    );
+    require_keys_eq!(
+        accounts.pool_quote_token_account.key(),
+        pool.pool_quote_token_account,
+        MmWalletError::InvalidPool
+    );
+    
+    // ═══ BALANCE & AMOUNT VALIDATION ═══
+    // Buys can spend native SOL plus wrapped SOL
+    let wrapped = accounts.pda_quote_account.amount;
+    if is_buy {
+        let available = accounts.pda_wallet
+            .lamports()
+            .saturating_sub(MIN_RENT_RESERVE)
+            .saturating_add(wrapped);
+        wallet.check_buy_amount(amount_in, available)?;
+    } else {
+        wallet.check_sell_amount(amount_in, accounts.pda_token_account.amount)?;
+    }
+    
+    // ═══ ON-CHAIN QUOTE ═══
+    let fees = SwapFees::try_from_global_config(accounts.global_config, &pool)?;
+    let reserves = PoolReserves {
+        base: accounts.pool_base_token_account.amount,
+        quote: accounts.pool_quote_token_account.amount,
+    };
+    let quoted_out = if is_buy {
+        reserves.quote_buy(amount_in, &fees)?
+    } else {
+        reserves.quote_sell(amount_in, &fees)?
+    };
+    
+    // ═══ SLIPPAGE PROTECTION ═══
+    let min_amount_out = wallet.min_output_from_quote(expected_out, quoted_out, max_slippage_bps)?;
+    
+    // ═══ WRAP SOL ═══
+    let shortfall = if is_buy { amount_in.saturating_sub(wrapped) } else { 0 };
+    if shortfall > 0 {
+        **accounts.pda_wallet.try_borrow_mut_lamports()? -= shortfall;
+        **accounts.pda_quote_account.to_account_info().try_borrow_mut_lamports()? += shortfall;
+        
+        token::sync_native(CpiContext::new(
+            accounts.token_program.to_account_info(),
+            SyncNative {
+                account: accounts.pda_quote_account.to_account_info(),
+            },
+        ))?;
+        accounts.pda_quote_account.reload()?;
+    }
+    
+    // ═══ BUILD CPI ═══
+    let data = if is_buy {
+        buy_data(min_amount_out, amount_in)
+    } else {
+        sell_data(amount_in, min_amount_out)
+    };
+    let ix = venue_instruction(PUMPSWAP_PROGRAM, accounts.remaining, data);
+    
+    let before = BalanceSnapshot::capture_wrapped(accounts.pda_quote_account, accounts.pda_token_account);
+    
+    wallet.invoke_signed(&ix, accounts.remaining)?;
+    
+    // ═══ VERIFY REALIZED AMOUNTS ═══
+    accounts.pda_token_account.reload()?;
+    accounts.pda_quote_account.reload()?;
+    let after = BalanceSnapshot::capture_wrapped(accounts.pda_quote_account, accounts.pda_token_account);
+    let (realized_in, realized_out) = before.realized(&after, is_buy);
+    verify_realized(realized_in, realized_out, amount_in, min_amount_out)?;
+    
+    // Post-trade pool price for equity valuation
+    let mark_price = PoolReserves::from_vaults(
+        &accounts.pool_base_token_account.to_account_info(),
+        &accounts.pool_quote_token_account.to_account_info(),
+    )?
+    .spot_price();
+    
+    Ok(Fill {
+        venue: Venue::PumpSwap,
+        side,
+        amount_in,
+        min_amount_out,
+        realized_in,
+        realized_out,
+        mark_price,
+    })
+}
+
+/// Unwrap the PDA's WSOL into native SOL after a sell, so withdrawals can reach the proceeds
+/// 
+/// Closes the WSOL account into the PDA and recreates it with the PDA as payer, using the
+/// native mint and programs from the trade's (already validated) remaining accounts.
+pub fn unwrap_quote<'info>(
+    wallet: &MmWallet,
+    pda_wallet: &AccountInfo<'info>,
+    pda_quote_account: &mut Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+    remaining: &[AccountInfo<'info>],
+) -> Result<()> {
+    let seed_owner = wallet.seed_owner;
+    let nonce_bytes = wallet.nonce.to_le_bytes();
+    let seeds = &[
+        b"mm_wallet",
+        seed_owner.as_ref(),
+        nonce_bytes.as_ref(),
+        &[wallet.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+    
+    token::close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        CloseAccount {
+            account: pda_quote_account.to_account_info(),
+            destination: pda_wallet.clone(),
+            authority: pda_wallet.clone(),
+        },
+        signer_seeds,
+    ))?;
+    
+    associated_token::create_idempotent(CpiContext::new_with_signer(
+        remaining[account_index::ASSOCIATED_TOKEN_PROGRAM].clone(),
+        associated_token::Create {
+            payer: pda_wallet.clone(),
+            associated_token: pda_quote_account.to_account_info(),
+            authority: pda_wallet.clone(),
+            mint: remaining[account_index::QUOTE_MINT].clone(),
+            system_program: remaining[account_index::SYSTEM_PROGRAM].clone(),
+            token_program: token_program.to_account_info(),
+        },
+        signer_seeds,
+    ))?;
+    pda_quote_account.reload()?;
+    
+    Ok(())
+}
+
+/// PumpSwap vault authority holding a coin creator's fees
+pub fn coin_creator_vault_authority(coin_creator: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"creator_vault", coin_creator.as_ref()], &PUMPSWAP_PROGRAM).0
+}
+
+/// Validate the remaining accounts of `collect_coin_creator_fee`: WSOL fees move from
+/// the PDA's creator vault to the PDA's WSOL account and nowhere else
+pub fn validate_creator_fee_accounts(
+    accounts: &[AccountInfo],
+    pda_wallet: &Pubkey,
+    pda_quote_account: &Pubkey,
+) -> Result<()> {
+    require!(
+        accounts.len() >= CREATOR_FEE_ACCOUNTS_LEN,
+        MmWalletError::InvalidRemainingAccounts
+    );
+    
+    let vault_authority = coin_creator_vault_authority(pda_wallet);
+    let layout = [
+        (creator_fee_index::QUOTE_MINT, native_mint::ID),
+        (creator_fee_index::QUOTE_TOKEN_PROGRAM, token::ID),
+        (creator_fee_index::COIN_CREATOR, *pda_wallet),
+        (creator_fee_index::COIN_CREATOR_VAULT_AUTHORITY, vault_authority),
+        (
+            creator_fee_index::COIN_CREATOR_VAULT_ATA,
+            get_associated_token_address(&vault_authority, &native_mint::ID),
+        ),
+        (creator_fee_index::COIN_CREATOR_TOKEN_ACCOUNT, *pda_quote_account),
+        (creator_fee_index::PROGRAM, PUMPSWAP_PROGRAM),
+    ];
+    for (index, expected) in layout.iter() {
+        require_remaining_key(accounts, *index, expected, MmWalletError::InvalidRemainingAccounts)?;
+    }
+    
+    Ok(())
+}
+
+/// Canonical pool for a Pump.fun-migrated mint (index 0, created by the Pump.fun pool authority)
+pub fn canonical_pool_address(mint: &Pubkey) -> Pubkey {
+    let (pool_authority, _) =
+        Pubkey::find_program_address(&[b"pool-authority", mint.as_ref()], &PUMP_FUN_PROGRAM);
+    
+    Pubkey::find_program_address(
+        &[
+            b"pool",
+            &0u16.to_le_bytes(),
+            pool_authority.as_ref(),
+            mint.as_ref(),
+            native_mint::ID.as_ref(),
+        ],
+        &PUMPSWAP_PROGRAM,
+    )
+    .0
+}
+
+/// Validate that the remaining accounts of a PumpSwap trade are bound to the wallet's mint,
+/// agree with the pool account and follow the venue's account ordering
+pub fn validate_swap_accounts(
+    accounts: &[AccountInfo],
+    mint: &Pubkey,
+    pda_wallet: &Pubkey,
+    pda_token_account: &Pubkey,
+    pda_quote_account: &Pubkey,
+    pool_key: &Pubkey,
+    pool: &PoolState,
+) -> Result<()> {
+    require!(
+        accounts.len() >= SWAP_ACCOUNTS_LEN,
+        MmWalletError::InvalidRemainingAccounts
+    );
+    
+    let pda_base_ata = get_associated_token_address(pda_wallet, mint);
+    let pda_quote_ata = get_associated_token_address(pda_wallet, &native_mint::ID);
+    require_keys_eq!(*pda_token_account, pda_base_ata, MmWalletError::TokenMintMismatch);
+    require_keys_eq!(*pda_quote_account, pda_quote_ata, MmWalletError::InvalidTokenAccount);
+    
+    // Pool must be the canonical pool for this mint, quoted in WSOL
+    require_keys_eq!(*pool_key, canonical_pool_address(mint), MmWalletError::TokenMintMismatch);
+    require_keys_eq!(pool.base_mint, *mint, MmWalletError::TokenMintMismatch);
+    require_keys_eq!(pool.quote_mint, native_mint::ID, MmWalletError::InvalidPool);
+    
+    let mint_bound = [
+        (account_index::POOL, *pool_key),
+        (account_index::BASE_MINT, *mint),
+        (account_index::USER_BASE_TOKEN_ACCOUNT, pda_base_ata),
+    ];
+    for (index, expected) in mint_bound.iter() {
+        require_remaining_key(accounts, *index, expected, MmWalletError::TokenMintMismatch)?;
+    }
+    
+    let layout = [
+        (account_index::USER, *pda_wallet),
+        (account_index::GLOBAL_CONFIG, PUMPSWAP_GLOBAL_CONFIG),
+        (account_index::QUOTE_MINT, native_mint::ID),
+        (account_index::USER_QUOTE_TOKEN_ACCOUNT, pda_quote_ata),
+        (account_index::POOL_BASE_TOKEN_ACCOUNT, pool.pool_base_token_account),
+        (account_index::POOL_QUOTE_TOKEN_ACCOUNT, pool.pool_quote_token_account),
+        (account_index::SYSTEM_PROGRAM, system_program::ID),
+        (account_index::ASSOCIATED_TOKEN_PROGRAM, associated_token::ID),
+        (account_index::PROGRAM, PUMPSWAP_PROGRAM),
+    ];
+    for (index, expected) in layout.iter() {
+        require_remaining_key(accounts, *index, expected, MmWalletError::InvalidRemainingAccounts)?;
+    }
+    
+    Ok(())
+}
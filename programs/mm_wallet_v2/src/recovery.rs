@@ -0,0 +1,154 @@
+// ═══════════════════════════════════════════════════════════════════════════════
+// SOCIAL RECOVERY
+// Recovery guardians rotate a lost owner key after a challenge period the owner can veto
+// ═══════════════════════════════════════════════════════════════════════════════
+
+use anchor_lang::prelude::*;
+
+use crate::MmWalletError;
+
+/// Maximum recovery guardians per wallet (support is a `u16` bitmask)
+pub const MAX_RECOVERY_GUARDIANS: usize = 10;
+
+/// Shortest challenge period (3 days)
+pub const MIN_RECOVERY_CHALLENGE_SECONDS: i64 = 3 * 24 * 60 * 60;
+
+/// Longest challenge period (90 days)
+pub const MAX_RECOVERY_CHALLENGE_SECONDS: i64 = 90 * 24 * 60 * 60;
+
+/// A wallet's recovery guardians and the recovery in progress, if any
+#[account]
+pub struct RecoveryConfig {
+    /// MM wallet this config can recover
+    pub wallet: Pubkey,
+    
+    /// PDA bump seed
+    pub bump: u8,
+    
+    /// Recovery guardians (distinct from `MmWallet.guardian`, which can only pause)
+    pub guardians: Vec<Pubkey>,
+    
+    /// Guardians needed to back a recovery
+    pub threshold: u8,
+    
+    /// Seconds the owner has to veto once a recovery reaches the threshold
+    pub challenge_seconds: i64,
+    
+    /// Owner the recovery in progress would install (default = none in progress)
+    pub new_owner: Pubkey,
+    
+    /// Bitmask of guardians backing the recovery, by position in `guardians`
+    pub approvals: u16,
+    
+    /// Unix timestamp the recovery was started
+    pub started_at: i64,
+    
+    /// Unix timestamp from which the recovery can execute (0 = threshold not reached)
+    pub executable_at: i64,
+    
+    /// Reserved space for future upgrades
+    pub reserved: [u8; 32],
+}
+
+impl RecoveryConfig {
+    pub const SIZE: usize = 8 +  // discriminator
+        32 +  // wallet
+        1 +   // bump
+        4 + 32 * MAX_RECOVERY_GUARDIANS +  // guardians
+        1 +   // threshold
+        8 +   // challenge_seconds
+        32 +  // new_owner
+        2 +   // approvals
+        8 +   // started_at
+        8 +   // executable_at
+        32;   // reserved
+    
+    /// Replace guardians, threshold and challenge period, dropping any recovery in progress
+    pub fn set_guardians(&mut self, guardians: Vec<Pubkey>, threshold: u8, challenge_seconds: i64) -> Result<()> {
+        require!(
+            !guardians.is_empty() && guardians.len() <= MAX_RECOVERY_GUARDIANS,
+            MmWalletError::InvalidRecoveryConfig
+        );
+        require!(
+            threshold >= 1 && threshold as usize <= guardians.len(),
+            MmWalletError::InvalidRecoveryConfig
+        );
+        require!(
+            (MIN_RECOVERY_CHALLENGE_SECONDS..=MAX_RECOVERY_CHALLENGE_SECONDS).contains(&challenge_seconds),
+            MmWalletError::InvalidRecoveryConfig
+        );
+        for (i, guardian) in guardians.iter().enumerate() {
+            require!(*guardian != Pubkey::default(), MmWalletError::InvalidRecoveryConfig);
+            require!(!guardians[..i].contains(guardian), MmWalletError::InvalidRecoveryConfig);
+        }
+        
+        self.guardians = guardians;
+        self.threshold = threshold;
+        self.challenge_seconds = challenge_seconds;
+        self.clear();
+        
+        Ok(())
+    }
+    
+    pub fn is_active(&self) -> bool {
+        self.new_owner != Pubkey::default()
+    }
+    
+    /// Open a recovery towards `new_owner`, backed by the starting guardian
+    pub fn start(&mut self, guardian: &Pubkey, new_owner: Pubkey, current_time: i64) -> Result<u32> {
+        require!(!self.is_active(), MmWalletError::RecoveryInProgress);
+        require!(new_owner != Pubkey::default(), MmWalletError::InvalidRecoveryConfig);
+        let bit = self.guardian_bit(guardian)?;
+        
+        self.new_owner = new_owner;
+        self.approvals = 0;
+        self.started_at = current_time;
+        self.executable_at = 0;
+        
+        self.back(bit, current_time)
+    }
+    
+    /// Add a guardian's backing; reaching the threshold starts the challenge period
+    pub fn support(&mut self, guardian: &Pubkey, current_time: i64) -> Result<u32> {
+        require!(self.is_active(), MmWalletError::NoRecoveryInProgress);
+        let bit = self.guardian_bit(guardian)?;
+        require!(self.approvals & bit == 0, MmWalletError::RecoveryAlreadySupported);
+        
+        self.back(bit, current_time)
+    }
+    
+    /// Whether the recovery has the threshold and has outlived the challenge period
+    pub fn is_ready(&self, current_time: i64) -> bool {
+        self.is_active() && self.executable_at != 0 && current_time >= self.executable_at
+    }
+    
+    /// Drop the recovery in progress
+    pub fn clear(&mut self) {
+        self.new_owner = Pubkey::default();
+        self.approvals = 0;
+        self.started_at = 0;
+        self.executable_at = 0;
+    }
+    
+    fn guardian_bit(&self, guardian: &Pubkey) -> Result<u16> {
+        let index = self.guardians
+            .iter()
+            .position(|g| g == guardian)
+            .ok_or(error!(MmWalletError::NotRecoveryGuardian))?;
+        
+        Ok(1u16 << index)
+    }
+    
+    fn back(&mut self, bit: u16, current_time: i64) -> Result<u32> {
+        self.approvals |= bit;
+        
+        let approvals = self.approvals.count_ones();
+        if self.executable_at == 0 && approvals >= self.threshold as u32 {
+            self.executable_at = current_time
+                .checked_add(self.challenge_seconds)
+                .ok_or(MmWalletError::MathOverflow)?;
+        }
+        
+        Ok(approvals)
+    }
+}
@@ -0,0 +1,272 @@
+// ═══════════════════════════════════════════════════════════════════════════════
+// RISK LIMITS
+// Owner-configured caps enforced on every trade, stored in a per-wallet PDA
+// ═══════════════════════════════════════════════════════════════════════════════
+
+use anchor_lang::prelude::*;
+
+use crate::{Fill, MmWalletError, TradeSide};
+
+/// Seconds covered by one notional bucket
+pub const BUCKET_SECONDS: i64 = 60 * 60;
+
+/// Number of buckets in the rolling window (24 x 1h = 24h)
+pub const WINDOW_BUCKETS: usize = 24;
+
+/// Rolling window for the liquidity commitment's net sell cap (24h)
+pub const COMMITMENT_WINDOW_SECONDS: i64 = 24 * 60 * 60;
+
+/// Owner-set risk caps (0 = unlimited)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct RiskLimitsConfig {
+    /// Max lamports spent on buys over the rolling 24h window
+    pub max_daily_buy_lamports: u64,
+    
+    /// Max lamports received from sells over the rolling 24h window
+    pub max_daily_sell_lamports: u64,
+    
+    /// Max lamports on the SOL leg of a single trade
+    pub max_trade_lamports: u64,
+    
+    /// Max token balance the PDA may hold after a buy
+    pub max_token_position: u64,
+    
+    /// Max tokens sold in a single trade
+    pub max_sell_tokens: u64,
+    
+    /// Drawdown from the equity high-water mark that pauses the wallet, in bps
+    pub max_drawdown_bps: u16,
+}
+
+impl RiskLimitsConfig {
+    pub fn validate(&self) -> Result<()> {
+        require!(self.max_drawdown_bps <= 10_000, MmWalletError::InvalidDrawdownThreshold);
+        
+        Ok(())
+    }
+}
+
+/// Per-wallet risk limits and rolling notional window
+#[account]
+pub struct RiskLimits {
+    /// MM wallet these limits apply to
+    pub wallet: Pubkey,
+    
+    /// PDA bump seed
+    pub bump: u8,
+    
+    /// Configured caps
+    pub limits: RiskLimitsConfig,
+    
+    /// Hour index (unix time / 3600) of the most recent bucket
+    pub current_bucket: i64,
+    
+    /// Buy notional per hour, indexed by hour % 24
+    pub buy_buckets: [u64; WINDOW_BUCKETS],
+    
+    /// Sell notional per hour, indexed by hour % 24
+    pub sell_buckets: [u64; WINDOW_BUCKETS],
+    
+    /// Highest post-trade equity seen (lamports, tokens marked at the venue price)
+    pub high_water_mark: u64,
+    
+    /// Commitment window index (unix time / 24h) of `commitment_net_sold`
+    pub commitment_window: i64,
+    
+    /// Token balance when the current commitment window opened
+    pub commitment_base: u64,
+    
+    /// Tokens sold minus tokens bought in the current commitment window
+    pub commitment_net_sold: i64,
+    
+    /// Same for the previous window, weighted into the rolling total
+    pub commitment_prev_net_sold: i64,
+    
+    /// Reserved space for future upgrades
+    pub reserved: [u8; 14],
+}
+
+impl RiskLimits {
+    pub const SIZE: usize = 8 +  // discriminator
+        32 +  // wallet
+        1 +   // bump
+        42 +  // limits
+        8 +   // current_bucket
+        8 * WINDOW_BUCKETS +  // buy_buckets
+        8 * WINDOW_BUCKETS +  // sell_buckets
+        8 +   // high_water_mark
+        8 +   // commitment_window
+        8 +   // commitment_base
+        8 +   // commitment_net_sold
+        8 +   // commitment_prev_net_sold
+        14;   // reserved
+    
+    /// Advance the window to `current_time`, clearing buckets that have aged out
+    pub fn roll(&mut self, current_time: i64) {
+        let bucket = current_time.div_euclid(BUCKET_SECONDS);
+        if bucket <= self.current_bucket {
+            return;
+        }
+        
+        let elapsed = bucket - self.current_bucket;
+        if elapsed >= WINDOW_BUCKETS as i64 {
+            self.buy_buckets = [0; WINDOW_BUCKETS];
+            self.sell_buckets = [0; WINDOW_BUCKETS];
+        } else {
+            for b in (self.current_bucket + 1)..=bucket {
+                let i = b.rem_euclid(WINDOW_BUCKETS as i64) as usize;
+                self.buy_buckets[i] = 0;
+                self.sell_buckets[i] = 0;
+            }
+        }
+        
+        self.current_bucket = bucket;
+    }
+    
+    /// Buy notional over the rolling window
+    pub fn daily_buy(&self) -> u64 {
+        self.buy_buckets.iter().fold(0u64, |acc, v| acc.saturating_add(*v))
+    }
+    
+    /// Sell notional over the rolling window
+    pub fn daily_sell(&self) -> u64 {
+        self.sell_buckets.iter().fold(0u64, |acc, v| acc.saturating_add(*v))
+    }
+    
+    /// Drawdown of `equity` from the high-water mark, in bps
+    pub fn drawdown_bps(&self, equity: u64) -> u64 {
+        if self.high_water_mark == 0 || equity >= self.high_water_mark {
+            return 0;
+        }
+        
+        ((self.high_water_mark - equity) as u128 * 10_000 / self.high_water_mark as u128) as u64
+    }
+    
+    /// Raise the high-water mark to `equity` if higher, and return the drawdown in bps
+    /// if it breaches the configured threshold
+    pub fn check_drawdown(&mut self, equity: u64) -> Option<u64> {
+        if equity > self.high_water_mark {
+            self.high_water_mark = equity;
+        }
+        
+        let drawdown = self.drawdown_bps(equity);
+        let threshold = self.limits.max_drawdown_bps as u64;
+        (threshold > 0 && drawdown > threshold).then_some(drawdown)
+    }
+    
+    /// Take SOL that left the wallet outside of a trade off the high-water mark,
+    /// so withdrawals and fee payouts don't read as drawdown
+    pub fn record_withdrawal(&mut self, amount: u64) {
+        self.high_water_mark = self.high_water_mark.saturating_sub(amount);
+    }
+    
+    /// Net tokens sold over the rolling 24h window
+    /// 
+    /// The previous window counts in proportion to how much of it the rolling window
+    /// still covers.
+    pub fn rolling_net_sold(&self, current_time: i64) -> i64 {
+        let window = current_time.div_euclid(COMMITMENT_WINDOW_SECONDS);
+        let (current, previous) = match window - self.commitment_window {
+            0 => (self.commitment_net_sold, self.commitment_prev_net_sold),
+            1 => (0, self.commitment_net_sold),
+            _ => (0, 0),
+        };
+        let remaining = COMMITMENT_WINDOW_SECONDS - current_time.rem_euclid(COMMITMENT_WINDOW_SECONDS);
+        
+        current.saturating_add(
+            ((previous as i128) * (remaining as i128) / COMMITMENT_WINDOW_SECONDS as i128) as i64,
+        )
+    }
+    
+    /// Record a fill against the liquidity commitment: net tokens sold over the rolling
+    /// window may not exceed `commitment_bps` of the inventory held when the window opened
+    /// 
+    /// `token_balance` is the PDA's token balance after the trade.
+    pub fn record_commitment(
+        &mut self,
+        fill: &Fill,
+        token_balance: u64,
+        commitment_bps: u16,
+        current_time: i64,
+    ) -> Result<()> {
+        let tokens = fill.token_amount();
+        let (balance_before, delta) = match fill.side {
+            TradeSide::Buy => (token_balance.saturating_sub(tokens), -(tokens as i64)),
+            TradeSide::Sell => (token_balance.saturating_add(tokens), tokens as i64),
+        };
+        
+        // ═══ ROLL WINDOW ═══
+        let window = current_time.div_euclid(COMMITMENT_WINDOW_SECONDS);
+        if window != self.commitment_window {
+            self.commitment_prev_net_sold = if window == self.commitment_window + 1 {
+                self.commitment_net_sold
+            } else {
+                0
+            };
+            self.commitment_net_sold = 0;
+            self.commitment_window = window;
+            self.commitment_base = balance_before;
+        }
+        
+        self.commitment_net_sold = self.commitment_net_sold.saturating_add(delta);
+        
+        // ═══ NET SELL CAP ═══
+        if fill.side == TradeSide::Sell {
+            let cap = (self.commitment_base as u128) * (commitment_bps as u128) / 10_000;
+            require!(
+                self.rolling_net_sold(current_time) as i128 <= cap as i128,
+                MmWalletError::CommitmentSellCapExceeded
+            );
+        }
+        
+        Ok(())
+    }
+    
+    /// Record a verified fill and enforce every cap
+    /// 
+    /// `token_balance` is the PDA's token balance after the trade.
+    pub fn record_fill(&mut self, fill: &Fill, token_balance: u64, current_time: i64) -> Result<()> {
+        let limits = self.limits;
+        let notional = fill.sol_amount();
+        
+        // ═══ PER-TRADE CAP ═══
+        require!(
+            limits.max_trade_lamports == 0 || notional <= limits.max_trade_lamports,
+            MmWalletError::TradeNotionalExceedsCap
+        );
+        
+        // ═══ ROLLING 24H CAPS ═══
+        self.roll(current_time);
+        let i = self.current_bucket.rem_euclid(WINDOW_BUCKETS as i64) as usize;
+        
+        match fill.side {
+            TradeSide::Buy => {
+                self.buy_buckets[i] = self.buy_buckets[i].saturating_add(notional);
+                require!(
+                    limits.max_daily_buy_lamports == 0 || self.daily_buy() <= limits.max_daily_buy_lamports,
+                    MmWalletError::DailyBuyCapExceeded
+                );
+                
+                // ═══ POSITION CAP ═══
+                require!(
+                    limits.max_token_position == 0 || token_balance <= limits.max_token_position,
+                    MmWalletError::PositionLimitExceeded
+                );
+            }
+            TradeSide::Sell => {
+                require!(
+                    limits.max_sell_tokens == 0 || fill.token_amount() <= limits.max_sell_tokens,
+                    MmWalletError::SellExceedsCap
+                );
+                
+                self.sell_buckets[i] = self.sell_buckets[i].saturating_add(notional);
+                require!(
+                    limits.max_daily_sell_lamports == 0 || self.daily_sell() <= limits.max_daily_sell_lamports,
+                    MmWalletError::DailySellCapExceeded
+                );
+            }
+        }
+        
+        Ok(())
+    }
+}
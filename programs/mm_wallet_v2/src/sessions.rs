@@ -0,0 +1,124 @@
+// ═══════════════════════════════════════════════════════════════════════════════
+// SESSION KEYS
+// Short-lived signers issued by the owner, bounded by expiry and a lamport allowance
+// ═══════════════════════════════════════════════════════════════════════════════
+
+use anchor_lang::prelude::*;
+
+use crate::operators::{Permissions, ROLE_ALL, ROLE_PAUSE};
+use crate::MmWalletError;
+
+/// Session key issued for one signer on one wallet
+#[account]
+pub struct SessionKey {
+    /// MM wallet this session acts on
+    pub wallet: Pubkey,
+    
+    /// Session signing key
+    pub key: Pubkey,
+    
+    /// PDA bump seed
+    pub bump: u8,
+    
+    /// Role bitmask (`ROLE_*`, pause excluded)
+    pub roles: u8,
+    
+    /// Unix timestamp after which the session is invalid (0 = no timestamp expiry)
+    pub expires_at: i64,
+    
+    /// Slot after which the session is invalid (0 = no slot expiry)
+    pub expires_at_slot: u64,
+    
+    /// Lamports of trade notional left to spend
+    pub allowance_lamports: u64,
+    
+    /// Lamports of trade notional spent so far
+    pub spent_lamports: u64,
+    
+    /// Set when the allowance runs out
+    pub revoked: bool,
+    
+    /// Creation timestamp
+    pub created_at: i64,
+    
+    /// Reserved space for future upgrades
+    pub reserved: [u8; 32],
+}
+
+impl SessionKey {
+    pub const SIZE: usize = 8 +  // discriminator
+        32 +  // wallet
+        32 +  // key
+        1 +   // bump
+        1 +   // roles
+        8 +   // expires_at
+        8 +   // expires_at_slot
+        8 +   // allowance_lamports
+        8 +   // spent_lamports
+        1 +   // revoked
+        8 +   // created_at
+        32;   // reserved
+    
+    /// Validate session parameters at issue time
+    pub fn validate_params(
+        roles: u8,
+        expires_at: i64,
+        expires_at_slot: u64,
+        allowance_lamports: u64,
+        clock: &Clock,
+    ) -> Result<()> {
+        require!(
+            roles != 0 && roles & !(ROLE_ALL & !ROLE_PAUSE) == 0,
+            MmWalletError::InvalidRoles
+        );
+        require!(
+            expires_at != 0 || expires_at_slot != 0,
+            MmWalletError::InvalidSessionExpiry
+        );
+        require!(
+            expires_at == 0 || expires_at > clock.unix_timestamp,
+            MmWalletError::InvalidSessionExpiry
+        );
+        require!(
+            expires_at_slot == 0 || expires_at_slot > clock.slot,
+            MmWalletError::InvalidSessionExpiry
+        );
+        require!(allowance_lamports > 0, MmWalletError::SessionAllowanceExceeded);
+        
+        Ok(())
+    }
+    
+    /// Whether the session has passed either expiry
+    pub fn is_expired(&self, clock: &Clock) -> bool {
+        (self.expires_at != 0 && clock.unix_timestamp >= self.expires_at)
+            || (self.expires_at_slot != 0 && clock.slot >= self.expires_at_slot)
+    }
+    
+    /// Permissions granted by a live session
+    pub fn permissions(&self, clock: &Clock) -> Result<Permissions> {
+        require!(!self.revoked, MmWalletError::SessionRevoked);
+        require!(!self.is_expired(clock), MmWalletError::SessionExpired);
+        
+        Ok(Permissions {
+            roles: self.roles,
+            max_slippage_bps: 0,
+            max_trade_lamports: 0,
+        })
+    }
+    
+    /// Charge trade notional against the allowance, revoking the session when it runs out
+    /// 
+    /// Returns `true` if this charge exhausted the allowance.
+    pub fn consume(&mut self, lamports: u64) -> Result<bool> {
+        require!(
+            lamports <= self.allowance_lamports,
+            MmWalletError::SessionAllowanceExceeded
+        );
+        
+        self.allowance_lamports -= lamports;
+        self.spent_lamports = self.spent_lamports.saturating_add(lamports);
+        self.revoked = self.allowance_lamports == 0;
+        
+        Ok(self.revoked)
+    }
+}
@@ -0,0 +1,159 @@
+// ═══════════════════════════════════════════════════════════════════════════════
+// TIMELOCK
+// Strategy, operator and session changes on locked wallets, queued behind a mandatory delay
+// ═══════════════════════════════════════════════════════════════════════════════
+
+use anchor_lang::prelude::*;
+
+use crate::multisig::ProposalAction;
+use crate::operators::{OperatorEntry, OperatorRegistry};
+use crate::protocol::ConfigBounds;
+use crate::sessions::SessionKey;
+use crate::{validate_config, MmWallet, MmWalletError, Strategy, StrategyConfig};
+
+/// Delay between queueing a config change and applying it (48 hours)
+pub const CONFIG_CHANGE_DELAY_SECONDS: i64 = 48 * 60 * 60;
+
+/// Kind codes recorded in config change events
+pub mod config_change_kind {
+    pub const STRATEGY: u8 = 0;
+    pub const OPERATOR: u8 = 1;
+    pub const ADD_OPERATOR: u8 = 2;
+    pub const UPDATE_OPERATOR: u8 = 3;
+    pub const CREATE_SESSION: u8 = 4;
+}
+
+/// A change that a locked wallet only takes after the delay
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub enum ConfigChange {
+    /// `update_strategy`
+    Strategy { strategy: Strategy, config: StrategyConfig },
+    
+    /// `set_operator`
+    Operator { operator: Pubkey },
+    
+    /// `add_operator`
+    AddOperator { entry: OperatorEntry },
+    
+    /// `update_operator`
+    UpdateOperator { entry: OperatorEntry },
+    
+    /// `create_session`
+    CreateSession {
+        session_key: Pubkey,
+        roles: u8,
+        expires_at: i64,
+        expires_at_slot: u64,
+        allowance_lamports: u64,
+    },
+}
+
+impl ConfigChange {
+    /// Largest variant: session key + roles + expiries + allowance
+    pub const SIZE: usize = 1 + 32 + 1 + 8 + 8 + 8;
+    
+    pub fn kind(&self) -> u8 {
+        match self {
+            ConfigChange::Strategy { .. } => config_change_kind::STRATEGY,
+            ConfigChange::Operator { .. } => config_change_kind::OPERATOR,
+            ConfigChange::AddOperator { .. } => config_change_kind::ADD_OPERATOR,
+            ConfigChange::UpdateOperator { .. } => config_change_kind::UPDATE_OPERATOR,
+            ConfigChange::CreateSession { .. } => config_change_kind::CREATE_SESSION,
+        }
+    }
+    
+    /// Multisig proposal a multisig wallet needs to queue this change
+    pub fn proposal_action(&self) -> ProposalAction {
+        match *self {
+            ConfigChange::Strategy { strategy, config } => ProposalAction::UpdateStrategy { strategy, config },
+            ConfigChange::Operator { operator } => ProposalAction::SetOperator { operator },
+            ConfigChange::AddOperator { entry } => ProposalAction::AddOperator { entry },
+            ConfigChange::UpdateOperator { entry } => ProposalAction::UpdateOperator { entry },
+            ConfigChange::CreateSession { session_key, roles, expires_at, expires_at_slot, allowance_lamports } => {
+                ProposalAction::CreateSession { session_key, roles, expires_at, expires_at_slot, allowance_lamports }
+            }
+        }
+    }
+    
+    /// Key of the session a `CreateSession` change issues (default for other changes)
+    pub fn session_key(&self) -> Pubkey {
+        match self {
+            ConfigChange::CreateSession { session_key, .. } => *session_key,
+            _ => Pubkey::default(),
+        }
+    }
+    
+    /// Same checks as the direct instruction (run at queue time and again at apply time)
+    pub fn validate(&self, bounds: &ConfigBounds, clock: &Clock) -> Result<()> {
+        match self {
+            ConfigChange::Strategy { config, .. } => validate_config(config, bounds),
+            ConfigChange::Operator { operator } => {
+                require!(*operator != Pubkey::default(), MmWalletError::InvalidOperator);
+                Ok(())
+            }
+            ConfigChange::AddOperator { entry } | ConfigChange::UpdateOperator { entry } => entry.validate(),
+            ConfigChange::CreateSession { roles, expires_at, expires_at_slot, allowance_lamports, .. } => {
+                SessionKey::validate_params(*roles, *expires_at, *expires_at_slot, *allowance_lamports, clock)
+            }
+        }
+    }
+    
+    /// Write the change to the wallet, or to its operator registry for registry changes
+    /// 
+    /// Sessions are issued by `apply_config_change` itself, which creates their account.
+    pub fn apply(&self, wallet: &mut MmWallet, registry: Option<&mut OperatorRegistry>) -> Result<()> {
+        match *self {
+            ConfigChange::Strategy { strategy, config } => {
+                wallet.strategy = strategy;
+                wallet.config = config;
+            }
+            ConfigChange::Operator { operator } => wallet.operator = operator,
+            ConfigChange::AddOperator { entry } => {
+                registry.ok_or(error!(MmWalletError::OperatorRegistryRequired))?.add(entry)?;
+            }
+            ConfigChange::UpdateOperator { entry } => {
+                registry.ok_or(error!(MmWalletError::OperatorRegistryRequired))?.update(entry)?;
+            }
+            ConfigChange::CreateSession { .. } => {}
+        }
+        
+        Ok(())
+    }
+}
+
+/// A wallet's queued config change (one at a time; cancel to replace it)
+#[account]
+pub struct PendingConfigChange {
+    /// MM wallet the change applies to
+    pub wallet: Pubkey,
+    
+    /// PDA bump seed
+    pub bump: u8,
+    
+    /// The queued change
+    pub change: ConfigChange,
+    
+    /// Unix timestamp the change was queued at
+    pub queued_at: i64,
+    
+    /// Unix timestamp from which the change can be applied
+    pub eta: i64,
+    
+    /// Reserved space for future upgrades
+    pub reserved: [u8; 32],
+}
+
+impl PendingConfigChange {
+    pub const SIZE: usize = 8 +  // discriminator
+        32 +  // wallet
+        1 +   // bump
+        ConfigChange::SIZE +  // change
+        8 +   // queued_at
+        8 +   // eta
+        32;   // reserved
+    
+    /// Whether the delay has elapsed
+    pub fn is_ready(&self, current_time: i64) -> bool {
+        current_time >= self.eta
+    }
+}
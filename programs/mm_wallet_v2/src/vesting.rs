@@ -0,0 +1,99 @@
+// ═══════════════════════════════════════════════════════════════════════════════
+// VESTING
+// Linear unlock of SOL principal or tokens: nothing before the cliff, all at the end
+// ═══════════════════════════════════════════════════════════════════════════════
+
+use anchor_lang::prelude::*;
+
+use crate::{MmWalletError, MAX_TOTAL_LOCK_SECONDS};
+
+/// Vesting schedule requested at `initialize` or `set_token_lock`, relative to the current time
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct VestingParams {
+    /// Seconds before anything vests (= `duration_seconds` for a plain cliff)
+    pub cliff_seconds: i64,
+    
+    /// Seconds until everything has vested
+    pub duration_seconds: i64,
+    
+    /// Amount subject to the schedule (lamports, or token base units for the token lock)
+    pub locked_principal: u64,
+}
+
+impl VestingParams {
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.duration_seconds > 0 && self.duration_seconds <= MAX_TOTAL_LOCK_SECONDS,
+            MmWalletError::InvalidVestingSchedule
+        );
+        require!(
+            (0..=self.duration_seconds).contains(&self.cliff_seconds),
+            MmWalletError::InvalidVestingSchedule
+        );
+        require!(self.locked_principal > 0, MmWalletError::InvalidVestingSchedule);
+        
+        Ok(())
+    }
+    
+    /// Absolute schedule starting at `start`
+    pub fn schedule(&self, start: i64) -> Result<VestingSchedule> {
+        self.validate()?;
+        
+        Ok(VestingSchedule {
+            start,
+            cliff: start.checked_add(self.cliff_seconds).ok_or(MmWalletError::MathOverflow)?,
+            end: start.checked_add(self.duration_seconds).ok_or(MmWalletError::MathOverflow)?,
+            locked_principal: self.locked_principal,
+        })
+    }
+}
+
+/// Linear vesting schedule stored on `MmWallet` (`end == 0` = no schedule)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct VestingSchedule {
+    /// Unix timestamp vesting is measured from
+    pub start: i64,
+    
+    /// Unix timestamp before which nothing is vested
+    pub cliff: i64,
+    
+    /// Unix timestamp at which everything is vested
+    pub end: i64,
+    
+    /// Amount subject to the schedule (lamports, or token base units for the token lock)
+    pub locked_principal: u64,
+}
+
+impl VestingSchedule {
+    pub const SIZE: usize = 8 + 8 + 8 + 8;
+    
+    pub fn is_set(&self) -> bool {
+        self.end != 0
+    }
+    
+    /// Principal vested at `current_time`
+    pub fn vested(&self, current_time: i64) -> u64 {
+        if !self.is_set() || current_time >= self.end {
+            return self.locked_principal;
+        }
+        if current_time < self.cliff || current_time <= self.start {
+            return 0;
+        }
+        
+        let elapsed = (current_time - self.start) as u128;
+        let duration = (self.end - self.start) as u128;
+        ((self.locked_principal as u128) * elapsed / duration) as u64
+    }
+    
+    /// Principal still locked at `current_time`
+    pub fn locked(&self, current_time: i64) -> u64 {
+        self.locked_principal.saturating_sub(self.vested(current_time))
+    }
+    
+    /// Push the end of an unfinished schedule back by `seconds` (never unlocks more)
+    pub fn extend(&mut self, seconds: i64) -> Result<()> {
+        self.end = self.end.checked_add(seconds).ok_or(MmWalletError::MathOverflow)?;
+        
+        Ok(())
+    }
+}